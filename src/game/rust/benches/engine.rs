@@ -0,0 +1,144 @@
+//! Benchmarks for the engine and the built-in players.
+//!
+//! A baseline is kept in `benches/baseline`. To see how a change compares to
+//! it, run:
+//!
+//!     CRITERION_HOME=benches/baseline cargo bench --bench engine -- --baseline main
+//!
+//! and after an intentional change in performance, store a new one with
+//! `--save-baseline main` instead.
+
+use chain_reaction::{
+    from_name, parse_position, ChainReaction, GameContext, PlayRandomly, Player, Pos, Runner,
+    STRATEGY_INFO,
+};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::time::Duration;
+
+const SIZES: [usize; 3] = [5, 8, 12];
+
+/// A 5x5 board where every cell of player 0 is one orb short of exploding,
+/// so that placing on the corner sets off a chain across the whole board.
+fn loaded_board() -> ChainReaction {
+    let size = 5;
+    let mut position = format!("position {} {} 2 0 2", size, size);
+    for y in 0..size {
+        position.push('\n');
+        let row: Vec<_> = (0..size)
+            .map(|x| {
+                let edges = [x == 0, x == size - 1, y == 0, y == size - 1];
+                let capacity = 4 - edges.iter().filter(|&&edge| edge).count();
+                if (x, y) == (size - 1, size - 1) {
+                    "1:1".to_string()
+                } else {
+                    format!("{}:0", capacity - 1)
+                }
+            })
+            .collect();
+        position += &row.join(" ");
+    }
+    parse_position(&position).unwrap()
+}
+
+/// A game on a `size` by `size` board after `plies` random moves.
+fn midgame(size: usize, plies: usize) -> ChainReaction {
+    let game = ChainReaction::new(size, size, 2).unwrap();
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    let mut moves = Vec::new();
+
+    for _ in 0..plies {
+        game.legal_moves_into(&mut moves);
+        if !game.active() || moves.is_empty() {
+            break;
+        }
+        game.place(&moves[rng.gen_range(0..moves.len())]).unwrap();
+    }
+    game
+}
+
+fn place(c: &mut Criterion) {
+    let mut group = c.benchmark_group("place");
+
+    group.bench_function("quiet", |b| {
+        b.iter_batched(
+            || ChainReaction::new(5, 5, 2).unwrap(),
+            |game| game.place(&Pos(2, 2)).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+
+    let loaded = loaded_board();
+    group.bench_function("chain_reaction", |b| {
+        b.iter_batched(
+            || loaded.clone(),
+            |game| game.place(&Pos(0, 0)).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+fn random_games(c: &mut Criterion) {
+    let mut group = c.benchmark_group("random_game");
+
+    for &size in &SIZES {
+        let id = format!("{}x{}", size, size);
+
+        group.bench_function(BenchmarkId::from_parameter(&id), |b| {
+            let players: Vec<Box<dyn Player>> =
+                vec![Box::new(PlayRandomly {}), Box::new(PlayRandomly {})];
+            let mut runner = Runner::new(size, size, players).unwrap();
+            runner.seed(1);
+            b.iter(|| runner.run(1).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+fn available_cells(c: &mut Criterion) {
+    let mut group = c.benchmark_group("available_cells");
+    let game = midgame(8, 30);
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+
+    group.bench_function("available_cells", |b| {
+        b.iter(|| GameContext::new(&game, &mut rng).available_cells())
+    });
+
+    let mut moves = Vec::new();
+    group.bench_function("legal_moves_into", |b| {
+        b.iter(|| game.legal_moves_into(&mut moves))
+    });
+
+    group.bench_function("legal_move_count", |b| b.iter(|| game.legal_move_count()));
+
+    group.finish();
+}
+
+fn players(c: &mut Criterion) {
+    let mut group = c.benchmark_group("players");
+    let game = midgame(8, 30);
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+
+    for info in STRATEGY_INFO.iter() {
+        let mut player = from_name(info.name).unwrap();
+        group.bench_function(info.name, |b| {
+            b.iter(|| player.play(GameContext::new(&game, &mut rng)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .sample_size(30)
+        .warm_up_time(Duration::from_secs(1))
+        .measurement_time(Duration::from_secs(3));
+    targets = place, random_games, available_cells, players
+}
+criterion_main!(benches);
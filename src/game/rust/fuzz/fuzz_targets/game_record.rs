@@ -0,0 +1,13 @@
+#![no_main]
+
+use chain_reaction::GameRecord;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    if let Ok(record) = GameRecord::parse(input) {
+        if let Ok(game) = record.replay() {
+            let formatted = GameRecord::from_game(&game).to_string();
+            GameRecord::parse(&formatted).expect("formatted record should parse");
+        }
+    }
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use chain_reaction::{format_position, parse_position};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    if let Ok(game) = parse_position(input) {
+        let formatted = format_position(&game);
+        let reparsed = parse_position(&formatted).expect("formatted position should parse");
+        assert_eq!(format_position(&reparsed), formatted);
+
+        for pos in game.legal_moves().take(4).collect::<Vec<_>>() {
+            let _ = game.place(&pos);
+        }
+    }
+});
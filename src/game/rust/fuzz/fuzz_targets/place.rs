@@ -0,0 +1,75 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use chain_reaction::{Array2D, ChainReaction, Pos, Resolution, Rules, Teams};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Arbitrary, Debug)]
+struct Game {
+    width: u8,
+    height: u8,
+    players: u8,
+    capacity_offset: u8,
+    keep_excess: bool,
+    depth_first: bool,
+    place_on_opponents: bool,
+    /// Capacities of the cells row by row, if the board has custom ones.
+    capacity: Option<Vec<u8>>,
+    /// Team of every player and whether explosions convert allies.
+    teams: Option<(Vec<u8>, bool)>,
+    moves: Vec<(u8, u8)>,
+}
+
+fuzz_target!(|input: Game| {
+    let (width, height) = (
+        usize::from(input.width % 16),
+        usize::from(input.height % 16),
+    );
+    let players = usize::from(input.players % 8);
+    let rules = Rules {
+        capacity_offset: u32::from(input.capacity_offset % 4),
+        keep_excess: input.keep_excess,
+        resolution: if input.depth_first {
+            Resolution::DepthFirst
+        } else {
+            Resolution::BreadthFirst
+        },
+        place_on_opponents: input.place_on_opponents,
+    };
+    let mut game = match ChainReaction::with_rules(width, height, players, rules) {
+        Ok(game) => game,
+        Err(_) => return,
+    };
+
+    // Capacity maps and teams that do not fit the game are rejected, in
+    // which case it goes on with the defaults.
+    if let Some(values) = &input.capacity {
+        let mut capacity = Array2D::new(1, Pos(width, height));
+        for (i, &c) in values.iter().take(width * height).enumerate() {
+            *capacity.get_mut(&Pos(i % width, i / width)).unwrap() = u32::from(c % 8);
+        }
+        let _ = game.set_capacity(capacity);
+    }
+    if let Some((assignment, convert_allies)) = &input.teams {
+        let _ = game.set_teams(Teams {
+            assignment: assignment
+                .iter()
+                .map(|&team| usize::from(team) % players)
+                .collect(),
+            convert_allies: *convert_allies,
+        });
+    }
+
+    for &(x, y) in &input.moves {
+        if !game.active() {
+            break;
+        }
+        if !game.player_is_alive(game.current_player()) || game.legal_move_count() == 0 {
+            game.skip_player();
+            continue;
+        }
+        // Moves are taken as they come, so most of them are off the board
+        // or on someone else's cell and must be rejected without a panic.
+        let _ = game.place(&Pos(usize::from(x), usize::from(y)));
+    }
+});
@@ -1,5 +1,6 @@
 use std::{cell::Cell, fmt};
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Array2D<T> {
     array: Vec<T>,
     width: usize,
@@ -10,7 +11,7 @@ impl<T: fmt::Display + Copy> fmt::Display for Array2D<Cell<T>> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for y in 0..self.height {
             if y != 0 {
-                write!(f, "\n")?;
+                writeln!(f)?;
             }
             for x in 0..self.width {
                 let index = y * self.width + x;
@@ -25,7 +26,7 @@ impl<T: fmt::Display + Copy> fmt::Display for Array2D<Cell<T>> {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Pos(pub usize, pub usize);
 
 impl<T: Clone> Array2D<T> {
@@ -39,16 +40,35 @@ impl<T: Clone> Array2D<T> {
         }
     }
 
-    pub fn get(&self, Pos(x, y): &Pos) -> Result<&T, &'static str> {
-        let index = y * self.width + x;
-        self.array.get(index).ok_or("Position is out of bounds.")
+    pub fn width(&self) -> usize {
+        self.width
     }
 
-    pub fn get_mut(&mut self, Pos(x, y): &Pos) -> Result<&mut T, &'static str> {
-        let index = y * self.width + x;
-        self.array
-            .get_mut(index)
-            .ok_or("Position is out of bounds.")
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Iterates over the elements row by row.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.array.iter()
+    }
+
+    pub fn get(&self, pos: &Pos) -> Result<&T, &'static str> {
+        let index = self.index(pos)?;
+        Ok(&self.array[index])
+    }
+
+    pub fn get_mut(&mut self, pos: &Pos) -> Result<&mut T, &'static str> {
+        let index = self.index(pos)?;
+        Ok(&mut self.array[index])
+    }
+
+    fn index(&self, &Pos(x, y): &Pos) -> Result<usize, &'static str> {
+        if x < self.width && y < self.height {
+            Ok(y * self.width + x)
+        } else {
+            Err("Position is out of bounds.")
+        }
     }
 }
 
@@ -64,4 +84,12 @@ mod tests {
         let arr = Array2D::new(1, Pos(2, 3));
         assert_eq!(arr.array, vec![1; 6]);
     }
+
+    #[test]
+    fn rejects_out_of_bounds() {
+        let arr = Array2D::new(0, Pos(3, 2));
+        assert!(arr.get(&Pos(2, 1)).is_ok());
+        assert!(arr.get(&Pos(3, 0)).is_err());
+        assert!(arr.get(&Pos(0, 2)).is_err());
+    }
 }
@@ -0,0 +1,343 @@
+//! Bitboards for the cell sets and masses of a `ChainReaction`.
+//!
+//! Boards of up to 16x16 cells keep their sets of empty and owned cells in
+//! fixed size bitsets, with 16 bits for every column whatever the height, so
+//! that they list cells in the same order as `Pos` and map bits to cells
+//! without dividing. They also keep the mass of every cell in bit planes and
+//! a mask of the neighbours of every cell, so that the orbs around a cell are
+//! counted with a few bitwise operations. Larger boards fall back to hash
+//! sets.
+
+use crate::array2d::Pos;
+use std::{
+    collections::{hash_set, HashSet},
+    rc::Rc,
+};
+
+/// Longest side of a board that fits in a bitset.
+pub(crate) const MAX_SIDE: usize = 16;
+
+const WORDS: usize = MAX_SIDE * MAX_SIDE / 64;
+
+/// Planes needed for the mass of a cell, which stays below `MAX_CAPACITY`.
+const PLANES: usize = 6;
+
+/// Whether a board is small enough to be kept in bitsets.
+pub(crate) fn fits(width: usize, height: usize) -> bool {
+    width <= MAX_SIDE && height <= MAX_SIDE
+}
+
+/// One bit per cell of a 16x16 board, indexed column by column.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Bits([u64; WORDS]);
+
+impl Bits {
+    fn insert(&mut self, index: usize) {
+        self.0[index / 64] |= 1 << (index % 64);
+    }
+
+    fn remove(&mut self, index: usize) {
+        self.0[index / 64] &= !(1 << (index % 64));
+    }
+
+    fn contains(&self, index: usize) -> bool {
+        index < WORDS * 64 && self.0[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.iter().all(|&word| word == 0)
+    }
+
+    pub(crate) fn union(mut self, other: &Bits) -> Bits {
+        for (word, other) in self.0.iter_mut().zip(&other.0) {
+            *word |= other;
+        }
+        self
+    }
+
+    fn intersection(mut self, other: &Bits) -> Bits {
+        for (word, other) in self.0.iter_mut().zip(&other.0) {
+            *word &= other;
+        }
+        self
+    }
+
+    fn difference(mut self, other: &Bits) -> Bits {
+        for (word, other) in self.0.iter_mut().zip(&other.0) {
+            *word &= !other;
+        }
+        self
+    }
+
+    /// Indices of the set bits in increasing order.
+    fn iter(&self) -> Ones {
+        Ones {
+            words: self.0,
+            word: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Ones {
+    words: [u64; WORDS],
+    word: usize,
+}
+
+impl Iterator for Ones {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.word < WORDS {
+            let bits = &mut self.words[self.word];
+            if *bits != 0 {
+                let bit = bits.trailing_zeros() as usize;
+                *bits &= *bits - 1;
+                return Some(self.word * 64 + bit);
+            }
+            self.word += 1;
+        }
+        None
+    }
+}
+
+fn index(&Pos(x, y): &Pos) -> Option<usize> {
+    Some(x * MAX_SIDE + y).filter(|_| x < MAX_SIDE && y < MAX_SIDE)
+}
+
+fn pos(index: usize) -> Pos {
+    Pos(index / MAX_SIDE, index % MAX_SIDE)
+}
+
+/// Cells of a board that fits in a bitset, listed in the same order as
+/// `Pos`.
+#[derive(Clone, Debug)]
+pub(crate) struct Cells(Ones);
+
+impl Cells {
+    pub(crate) fn new(bits: &Bits) -> Cells {
+        Cells(bits.iter())
+    }
+}
+
+impl Iterator for Cells {
+    type Item = Pos;
+
+    fn next(&mut self) -> Option<Pos> {
+        self.0.next().map(pos)
+    }
+}
+
+/// A set of cells, such as the empty cells of a game or those of a player.
+#[derive(Clone, Debug)]
+pub struct CellSet(Storage);
+
+#[derive(Clone, Debug)]
+enum Storage {
+    Packed(Bits),
+    Hashed(HashSet<Pos>),
+}
+
+impl CellSet {
+    /// No cells, kept in a bitset if `packed`, in which case the board must
+    /// fit one.
+    pub(crate) fn new(packed: bool) -> CellSet {
+        CellSet(if packed {
+            Storage::Packed(Bits::default())
+        } else {
+            Storage::Hashed(HashSet::new())
+        })
+    }
+
+    /// Every cell of the board.
+    pub(crate) fn full(width: usize, height: usize, packed: bool) -> CellSet {
+        let mut cells = CellSet::new(packed);
+        for x in 0..width {
+            for y in 0..height {
+                cells.insert(Pos(x, y));
+            }
+        }
+        cells
+    }
+
+    pub fn contains(&self, pos: &Pos) -> bool {
+        match &self.0 {
+            Storage::Packed(bits) => index(pos).is_some_and(|index| bits.contains(index)),
+            Storage::Hashed(cells) => cells.contains(pos),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.0 {
+            Storage::Packed(bits) => bits.len(),
+            Storage::Hashed(cells) => cells.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match &self.0 {
+            Storage::Packed(bits) => bits.is_empty(),
+            Storage::Hashed(cells) => cells.is_empty(),
+        }
+    }
+
+    /// The cells in the set. Bitsets list them in the same order as `Pos`,
+    /// hash sets in no particular order.
+    pub fn iter(&self) -> CellIter<'_> {
+        CellIter(match &self.0 {
+            Storage::Packed(bits) => IterInner::Packed(Cells::new(bits)),
+            Storage::Hashed(cells) => IterInner::Hashed(cells.iter()),
+        })
+    }
+
+    pub(crate) fn insert(&mut self, pos: Pos) {
+        match &mut self.0 {
+            Storage::Packed(bits) => {
+                if let Some(index) = index(&pos) {
+                    bits.insert(index);
+                }
+            }
+            Storage::Hashed(cells) => {
+                cells.insert(pos);
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, pos: &Pos) {
+        match &mut self.0 {
+            Storage::Packed(bits) => {
+                if let Some(index) = index(pos) {
+                    bits.remove(index);
+                }
+            }
+            Storage::Hashed(cells) => {
+                cells.remove(pos);
+            }
+        }
+    }
+
+    /// The bitset holding the cells, unless they are kept in a hash set.
+    pub(crate) fn bits(&self) -> Option<&Bits> {
+        match &self.0 {
+            Storage::Packed(bits) => Some(bits),
+            Storage::Hashed(_) => None,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a CellSet {
+    type Item = Pos;
+    type IntoIter = CellIter<'a>;
+
+    fn into_iter(self) -> CellIter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the cells of a `CellSet`.
+#[derive(Clone, Debug)]
+pub struct CellIter<'a>(IterInner<'a>);
+
+#[derive(Clone, Debug)]
+enum IterInner<'a> {
+    Packed(Cells),
+    Hashed(hash_set::Iter<'a, Pos>),
+}
+
+impl Iterator for CellIter<'_> {
+    type Item = Pos;
+
+    fn next(&mut self) -> Option<Pos> {
+        match &mut self.0 {
+            IterInner::Packed(cells) => cells.next(),
+            IterInner::Hashed(cells) => cells.next().copied(),
+        }
+    }
+}
+
+/// The mass of every cell in bit planes, along with the neighbours of every
+/// cell, for a board that fits in a bitset.
+#[derive(Clone, Debug)]
+pub(crate) struct Planes {
+    /// Bit `i` of the mass of every cell in plane `i`.
+    mass: [Bits; PLANES],
+    /// Neighbours of every cell, by its index in a bitset. They only depend
+    /// on the board size, so clones of a game share them.
+    neighbors: Rc<[Bits]>,
+}
+
+impl Planes {
+    /// Planes for an empty board, or `None` if it does not fit in a bitset.
+    pub(crate) fn new(width: usize, height: usize) -> Option<Planes> {
+        if !fits(width, height) {
+            return None;
+        }
+
+        let mut neighbors = vec![Bits::default(); width * MAX_SIDE];
+        for x in 0..width {
+            for y in 0..height {
+                let mask = &mut neighbors[x * MAX_SIDE + y];
+                if x > 0 {
+                    mask.insert((x - 1) * MAX_SIDE + y);
+                }
+                if x + 1 < width {
+                    mask.insert((x + 1) * MAX_SIDE + y);
+                }
+                if y > 0 {
+                    mask.insert(x * MAX_SIDE + y - 1);
+                }
+                if y + 1 < height {
+                    mask.insert(x * MAX_SIDE + y + 1);
+                }
+            }
+        }
+
+        Some(Planes {
+            mass: [Bits::default(); PLANES],
+            neighbors: neighbors.into(),
+        })
+    }
+
+    pub(crate) fn set_mass(&mut self, pos: &Pos, mass: u32) {
+        let index = pos.0 * MAX_SIDE + pos.1;
+        let (word, bit) = (index / 64, index % 64);
+        for (i, plane) in self.mass.iter_mut().enumerate() {
+            let word = &mut plane.0[word];
+            *word = (*word & !(1 << bit)) | (u64::from((mass >> i) & 1) << bit);
+        }
+    }
+
+    /// Orbs on the cells within `radius` steps of `pos`, not counting `pos`
+    /// itself.
+    pub(crate) fn mass_within(&self, pos: &Pos, radius: usize) -> u32 {
+        let center = pos.0 * MAX_SIDE + pos.1;
+        let mut area = Bits::default();
+        area.insert(center);
+
+        let mut edge = area;
+        for _ in 0..radius {
+            let reached = edge
+                .iter()
+                .fold(Bits::default(), |reached, index| {
+                    reached.union(&self.neighbors[index])
+                })
+                .difference(&area);
+            if reached.is_empty() {
+                break;
+            }
+            area = area.union(&reached);
+            edge = reached;
+        }
+        area.remove(center);
+
+        self.mass
+            .iter()
+            .enumerate()
+            .map(|(i, plane)| (plane.intersection(&area).len() as u32) << i)
+            .sum()
+    }
+}
@@ -0,0 +1,407 @@
+use crate::array2d::Pos;
+use crate::chain_reaction::Move;
+use crate::notation::{parse_number, parse_pos};
+use crate::runner::{GameContext, Player, Runner};
+use std::{collections::HashMap, fmt, rc::Rc};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BookEntry {
+    pub games: u32,
+    pub wins: u32,
+}
+
+impl BookEntry {
+    /// Fraction of games won by the player who made the last move of the line.
+    pub fn win_rate(&self) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            f64::from(self.wins) / f64::from(self.games)
+        }
+    }
+}
+
+/// Win rates for opening move sequences, keyed by the canonical form of each
+/// sequence so that lines which are mirror images or rotations of each other
+/// share statistics.
+pub struct OpeningBook {
+    width: usize,
+    height: usize,
+    players: usize,
+    depth: usize,
+    entries: HashMap<Vec<Pos>, BookEntry>,
+}
+
+impl OpeningBook {
+    pub fn new(
+        width: usize,
+        height: usize,
+        players: usize,
+        depth: usize,
+    ) -> Result<OpeningBook, &'static str> {
+        if width == 0 {
+            Err("Width cannot be zero.")
+        } else if height == 0 {
+            Err("Height cannot be zero.")
+        } else if players == 0 {
+            Err("Player count cannot be zero.")
+        } else if depth == 0 {
+            Err("Book depth cannot be zero.")
+        } else {
+            Ok(OpeningBook {
+                width,
+                height,
+                players,
+                depth,
+                entries: HashMap::new(),
+            })
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn players(&self) -> usize {
+        self.players
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds a finished game to the book. Every prefix of the game up to the
+    /// book depth is credited with a win if its last move was made by `winner`.
+    /// Drawn and aborted games have no winner.
+    pub fn record(&mut self, moves: &[Move], winner: Option<usize>) {
+        let mut line = Vec::with_capacity(self.depth);
+
+        for m in moves.iter().take(self.depth) {
+            line.push(m.pos);
+
+            let entry = self.entries.entry(self.canonical(&line)).or_default();
+            entry.games += 1;
+            if Some(m.player) == winner {
+                entry.wins += 1;
+            }
+        }
+    }
+
+    pub fn lookup(&self, line: &[Pos]) -> Option<&BookEntry> {
+        self.entries.get(&self.canonical(line))
+    }
+
+    /// Picks the continuation of `line` with the best win rate among
+    /// `candidates`, ignoring entries backed by fewer than `min_games` games.
+    pub fn best_move<I>(&self, line: &[Pos], candidates: I, min_games: u32) -> Option<Pos>
+    where
+        I: IntoIterator<Item = Pos>,
+    {
+        if line.len() >= self.depth {
+            return None;
+        }
+
+        let mut next = line.to_vec();
+        let mut best: Option<(Pos, BookEntry)> = None;
+
+        for pos in candidates {
+            next.push(pos);
+            let entry = self.lookup(&next).copied();
+            next.pop();
+
+            let entry = match entry {
+                Some(entry) if entry.games >= min_games && entry.games > 0 => entry,
+                _ => continue,
+            };
+
+            let better = match best {
+                None => true,
+                Some((best_pos, best_entry)) => {
+                    let (a, b) = (
+                        u64::from(entry.wins) * u64::from(best_entry.games),
+                        u64::from(best_entry.wins) * u64::from(entry.games),
+                    );
+                    a > b || (a == b && (entry.games, best_pos) > (best_entry.games, pos))
+                }
+            };
+
+            if better {
+                best = Some((pos, entry));
+            }
+        }
+
+        best.map(|(pos, _)| pos)
+    }
+
+    fn symmetries(&self) -> usize {
+        if self.width == self.height {
+            8
+        } else {
+            4
+        }
+    }
+
+    /// Applies one of the board's symmetries. Bit 2 transposes the board
+    /// (square boards only), bit 0 mirrors it horizontally and bit 1 vertically.
+    fn transform(&self, Pos(x, y): Pos, symmetry: usize) -> Pos {
+        let (x, y) = if symmetry & 4 != 0 { (y, x) } else { (x, y) };
+        let x = if symmetry & 1 != 0 {
+            self.width - 1 - x
+        } else {
+            x
+        };
+        let y = if symmetry & 2 != 0 {
+            self.height - 1 - y
+        } else {
+            y
+        };
+        Pos(x, y)
+    }
+
+    fn canonical(&self, line: &[Pos]) -> Vec<Pos> {
+        (0..self.symmetries())
+            .map(|s| line.iter().map(|&pos| self.transform(pos, s)).collect())
+            .min()
+            .unwrap_or_default()
+    }
+
+    /// Parses a book previously written with its `Display` implementation.
+    pub fn parse(input: &str) -> Result<OpeningBook, &'static str> {
+        let mut lines = input.lines().filter(|l| !l.trim().is_empty());
+
+        let header: Vec<_> = lines
+            .next()
+            .ok_or("Book is empty.")?
+            .split_whitespace()
+            .collect();
+
+        let mut book = match header.as_slice() {
+            ["book", width, height, players, depth] => OpeningBook::new(
+                parse_number(width)?,
+                parse_number(height)?,
+                parse_number(players)?,
+                parse_number(depth)?,
+            )?,
+            _ => return Err("Invalid book header."),
+        };
+
+        for line in lines {
+            let fields: Vec<_> = line.split_whitespace().collect();
+            if fields.len() < 3 {
+                return Err("Invalid book entry.");
+            }
+
+            let (moves, counts) = fields.split_at(fields.len() - 2);
+            let moves = moves
+                .iter()
+                .map(|m| parse_pos(m))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if moves.len() > book.depth {
+                return Err("Book entry is deeper than the book.");
+            }
+            if moves
+                .iter()
+                .any(|&Pos(x, y)| x >= book.width || y >= book.height)
+            {
+                return Err("Position is out of bounds.");
+            }
+
+            let entry = BookEntry {
+                games: parse_number(counts[0])?,
+                wins: parse_number(counts[1])?,
+            };
+            if entry.wins > entry.games {
+                return Err("Book entry has more wins than games.");
+            }
+
+            book.entries.insert(book.canonical(&moves), entry);
+        }
+
+        Ok(book)
+    }
+}
+
+impl fmt::Display for OpeningBook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "book {} {} {} {}",
+            self.width, self.height, self.players, self.depth
+        )?;
+
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by(|a, b| a.0.len().cmp(&b.0.len()).then(a.0.cmp(b.0)));
+
+        for (line, entry) in entries {
+            writeln!(f)?;
+            for Pos(x, y) in line {
+                write!(f, "{},{} ", x, y)?;
+            }
+            write!(f, "{} {}", entry.games, entry.wins)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Builds an opening book by letting a set of players play against
+/// themselves through a `Runner`.
+pub struct BookBuilder {
+    book: OpeningBook,
+}
+
+impl BookBuilder {
+    pub fn new(
+        width: usize,
+        height: usize,
+        players: usize,
+        depth: usize,
+    ) -> Result<BookBuilder, &'static str> {
+        Ok(BookBuilder {
+            book: OpeningBook::new(width, height, players, depth)?,
+        })
+    }
+
+    /// Continues building from an existing book.
+    pub fn from_book(book: OpeningBook) -> BookBuilder {
+        BookBuilder { book }
+    }
+
+    pub fn self_play(
+        &mut self,
+        players: Vec<Box<dyn Player>>,
+        times: u32,
+    ) -> Result<(), &'static str> {
+        if players.len() != self.book.players {
+            return Err("Player count does not match the book.");
+        }
+
+        let mut runner = Runner::new(self.book.width, self.book.height, players)?;
+
+        for _ in 0..times {
+            runner.run(1)?;
+            let game = runner.game();
+            self.book.record(&game.history(), game.winner().ok());
+        }
+
+        Ok(())
+    }
+
+    pub fn build(self) -> OpeningBook {
+        self.book
+    }
+}
+
+/// Plays the best known move while the game is still covered by the book,
+/// and defers to `inner` once it leaves it.
+pub struct BookPlayer {
+    book: Rc<OpeningBook>,
+    inner: Box<dyn Player>,
+    min_games: u32,
+}
+
+impl BookPlayer {
+    pub fn new(book: Rc<OpeningBook>, inner: Box<dyn Player>, min_games: u32) -> Self {
+        BookPlayer {
+            book,
+            inner,
+            min_games,
+        }
+    }
+
+    fn book_move(&self, ctx: &GameContext) -> Option<Pos> {
+        let book = &self.book;
+        if ctx.width() != book.width()
+            || ctx.height() != book.height()
+            || ctx.players() != book.players()
+        {
+            return None;
+        }
+
+        let line: Vec<_> = ctx.history().iter().map(|m| m.pos).collect();
+        book.best_move(&line, ctx.available_cells(), self.min_games)
+    }
+}
+
+impl Player for BookPlayer {
+    fn play(&mut self, ctx: GameContext) -> Result<Pos, &'static str> {
+        match self.book_move(&ctx) {
+            Some(pos) => Ok(pos),
+            None => self.inner.play(ctx),
+        }
+    }
+
+    fn save_state(&self) -> String {
+        self.inner.save_state()
+    }
+
+    fn load_state(&mut self, state: &str) -> Result<(), &'static str> {
+        self.inner.load_state(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(positions: &[(usize, usize)]) -> Vec<Move> {
+        positions
+            .iter()
+            .enumerate()
+            .map(|(i, &(x, y))| Move {
+                player: i % 2,
+                pos: Pos(x, y),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn merges_symmetric_lines() {
+        let mut book = OpeningBook::new(3, 3, 2, 2).unwrap();
+        book.record(&moves(&[(0, 0), (1, 1)]), Some(0));
+        book.record(&moves(&[(2, 2), (1, 1)]), Some(1));
+        book.record(&moves(&[(0, 2), (1, 1)]), Some(0));
+
+        assert_eq!(book.len(), 2);
+        assert_eq!(
+            book.lookup(&[Pos(2, 0)]),
+            Some(&BookEntry { games: 3, wins: 2 })
+        );
+        assert_eq!(
+            book.lookup(&[Pos(2, 2), Pos(1, 1)]),
+            Some(&BookEntry { games: 3, wins: 1 })
+        );
+    }
+
+    #[test]
+    fn rectangular_boards_are_not_transposed() {
+        let mut book = OpeningBook::new(3, 2, 2, 1).unwrap();
+        book.record(&moves(&[(1, 0)]), Some(0));
+
+        assert!(book.lookup(&[Pos(1, 1)]).is_some());
+        assert!(book.lookup(&[Pos(0, 1)]).is_none());
+    }
+
+    #[test]
+    fn round_trips_through_text() {
+        let mut book = OpeningBook::new(4, 4, 2, 3).unwrap();
+        book.record(&moves(&[(0, 0), (3, 1), (2, 2)]), Some(1));
+        book.record(&moves(&[(1, 2), (0, 0)]), Some(0));
+
+        let parsed = OpeningBook::parse(&book.to_string()).unwrap();
+        assert_eq!(parsed.to_string(), book.to_string());
+    }
+}
@@ -1,20 +1,47 @@
 use crate::array2d::{Array2D, Pos};
+use crate::bitboard::{self, Bits, CellSet, Cells, Planes};
 
 use std::{
-    cell::{Cell, RefCell},
-    collections::HashSet,
-    convert::TryInto,
+    cell::{Cell, Ref, RefCell},
     fmt,
-    iter::FromIterator,
 };
 
-#[derive(Clone, Copy)]
+/// Largest board, in cells, that a game can be set up on.
+pub const MAX_CELLS: usize = 1 << 16;
+/// Largest number of players in a game.
+pub const MAX_PLAYERS: usize = 256;
+/// Largest capacity of a single cell. Along with `MAX_CELLS`, this bounds
+/// how long an explosion on a board that creates orbs can run before it is
+/// found to overflow.
+pub const MAX_CAPACITY: u32 = 64;
+
+/// Checks the dimensions of a game before anything is allocated for it.
+pub(crate) fn check_size(width: usize, height: usize, players: usize) -> Result<(), &'static str> {
+    if width == 0 {
+        Err("Width cannot be zero.")
+    } else if height == 0 {
+        Err("Height cannot be zero.")
+    } else if players == 0 {
+        Err("Player count cannot be zero.")
+    } else if width
+        .checked_mul(height)
+        .is_none_or(|cells| cells > MAX_CELLS)
+    {
+        Err("Board is too large.")
+    } else if players > MAX_PLAYERS {
+        Err("Too many players.")
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct FieldData {
     pub owner: usize,
     pub count: u32,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Field {
     Owned(FieldData),
     Empty,
@@ -29,6 +56,244 @@ impl fmt::Display for Field {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub player: usize,
+    pub pos: Pos,
+}
+
+/// Everything that changed on the board as a result of a single placement.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Explosion {
+    /// Cells that exploded, grouped by wave. If the placed cell exploded, it
+    /// forms the first wave on its own.
+    pub waves: Vec<Vec<Pos>>,
+    /// Cells that were taken over from other players.
+    pub captured: Vec<Pos>,
+    /// Orbs in flight that were taken off the board, along with their
+    /// owners' mass, when the explosion was found to repeat itself. An
+    /// explosion that ends the game leaves its orbs in flight instead, still
+    /// counted towards their owners.
+    pub dropped: usize,
+}
+
+/// Caps that keep games from running forever on rules or boards where mass
+/// can circulate indefinitely, or with bots that never finish a game.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Limits {
+    /// Waves a single explosion may take, counting the placed cell. The game
+    /// is declared a draw as soon as an explosion needs more.
+    pub max_waves: Option<u32>,
+    /// Moves after which an unfinished game is aborted.
+    pub max_plies: Option<u32>,
+}
+
+/// Alliances between players. Explosions leave allied cells alone, and the
+/// game ends once a single team is left.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Teams {
+    /// Team of every player, numbered from 0.
+    pub assignment: Vec<usize>,
+    /// Let explosions take over allied cells like enemy ones. Otherwise an
+    /// orb landing on an ally's cell is handed over to that ally.
+    pub convert_allies: bool,
+}
+
+impl Teams {
+    /// Every player on a team of their own, as in a regular game.
+    pub fn solo(players: usize) -> Teams {
+        Teams {
+            assignment: (0..players).collect(),
+            convert_allies: false,
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.assignment.iter().max().map_or(0, |&team| team + 1)
+    }
+
+    pub fn is_solo(&self) -> bool {
+        self.count() == self.assignment.len()
+    }
+
+    pub(crate) fn validate(&self, players: usize) -> Result<(), &'static str> {
+        if self.assignment.len() != players {
+            return Err("Team assignment does not match the player count.");
+        }
+        if self.assignment.iter().any(|&team| team >= players)
+            || (0..self.count()).any(|team| !self.assignment.contains(&team))
+        {
+            return Err("Teams must be numbered from 0 without gaps.");
+        }
+        Ok(())
+    }
+}
+
+/// Legal moves, listed straight from the cell sets on boards that fit in a
+/// bitset and found by scanning the grid on the others. The scan is boxed so
+/// that it does not slow down the loop over the bitsets.
+enum LegalMoves<'a> {
+    Packed(Cells),
+    Scanned(Box<dyn Iterator<Item = Pos> + 'a>),
+}
+
+impl Iterator for LegalMoves<'_> {
+    type Item = Pos;
+
+    fn next(&mut self) -> Option<Pos> {
+        match self {
+            LegalMoves::Packed(cells) => cells.next(),
+            LegalMoves::Scanned(cells) => cells.next(),
+        }
+    }
+}
+
+/// Board contents along with the orbs in flight and their owners, used to
+/// notice when an explosion repeats itself.
+type ExplosionState = (Vec<Field>, Vec<(Pos, usize)>);
+
+/// Watches an explosion for signs that it will never come to rest.
+struct Runaway<S> {
+    /// Orbs the board holds when every cell is one short of exploding, if
+    /// some cell has fewer neighbours than its capacity so that explosions
+    /// can create orbs.
+    room: Option<u64>,
+    /// State the explosion is compared against, along with the orbs in
+    /// flight and the total mass at the time.
+    saved: Option<(usize, u64, S)>,
+    steps: usize,
+    power: usize,
+}
+
+impl<S: PartialEq> Runaway<S> {
+    fn new(room: Option<u64>) -> Runaway<S> {
+        Runaway {
+            room,
+            saved: None,
+            steps: 0,
+            power: 1,
+        }
+    }
+
+    /// Whether `mass`, counting the orbs in flight, no longer fits on a
+    /// board that creates orbs. Such an explosion never repeats itself, so
+    /// it would otherwise go on forever.
+    fn overflowing(&self, mass: u64) -> bool {
+        self.room.is_some_and(|room| mass > room)
+    }
+
+    /// Whether the explosion is back in a state it has been in before.
+    ///
+    /// Uses Brent's algorithm, so only one earlier state is kept, and
+    /// `state` is only built when the orbs in flight and the mass match
+    /// those of that state or it is time to replace it. A repetition is
+    /// found within a couple of cycle lengths of its start.
+    fn repeats(&mut self, pending: usize, mass: u64, state: impl Fn() -> S) -> bool {
+        let mut current = None;
+        if let Some((saved_pending, saved_mass, saved)) = &self.saved {
+            if (*saved_pending, *saved_mass) == (pending, mass) {
+                let state = state();
+                if state == *saved {
+                    return true;
+                }
+                current = Some(state);
+            }
+        }
+
+        self.steps += 1;
+        if self.steps == self.power {
+            self.saved = Some((pending, mass, current.unwrap_or_else(state)));
+            self.steps = 0;
+            self.power *= 2;
+        }
+        false
+    }
+}
+
+/// Order in which the orbs of an explosion are handed out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Resolution {
+    /// All cells that reach capacity in a wave explode together.
+    BreadthFirst,
+    /// Each explosion is followed through to the end before moving on to
+    /// the next orb, so every exploding cell forms a wave of its own.
+    DepthFirst,
+}
+
+/// Variations on the rules, fixed when a game is created.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rules {
+    /// Added to the neighbour count to get the capacity of a cell. The
+    /// classic rules use 0, so that a cell explodes once it holds as many
+    /// orbs as it has neighbours.
+    pub capacity_offset: u32,
+    /// Let an exploding cell keep the orbs it does not send to its
+    /// neighbours instead of becoming empty.
+    pub keep_excess: bool,
+    pub resolution: Resolution,
+    /// Allow placing on an opponent's cell, taking it over along with its
+    /// orbs.
+    pub place_on_opponents: bool,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            capacity_offset: 0,
+            keep_excess: false,
+            resolution: Resolution::BreadthFirst,
+            place_on_opponents: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    /// The player's team won. In team games this is the first member of the
+    /// winning team still in the game.
+    Win(usize),
+    /// An explosion ran past `Limits::max_waves`, or an explosion resolved
+    /// depth first grew past what the board can hold while enemies were
+    /// still on it, so that it would never settle. The latter needs no
+    /// limits to be set.
+    Draw,
+    /// The game ran past `Limits::max_plies`.
+    Aborted,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Elimination {
+    pub player: usize,
+    /// Number of the move that eliminated the player, counting from 1.
+    pub ply: u32,
+    /// Player who made that move. `None` for players who were already out
+    /// when a position was set up, in which case `ply` is the position's
+    /// move count.
+    pub by: Option<usize>,
+}
+
+/// How a finished game ended, along with the final standings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameOutcome {
+    pub result: Outcome,
+    /// Players from first to last place. Players still in the game come
+    /// first, ordered by mass, followed by the eliminated players starting
+    /// with the last one to go.
+    pub ranking: Vec<usize>,
+    /// Eliminations in the order they happened.
+    pub eliminations: Vec<Elimination>,
+}
+
+impl GameOutcome {
+    pub fn winner(&self) -> Option<usize> {
+        match self.result {
+            Outcome::Win(winner) => Some(winner),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct ChainReaction {
     width: usize,
     height: usize,
@@ -36,42 +301,208 @@ pub struct ChainReaction {
     current_player: Cell<usize>,
     move_count: Cell<u32>,
 
-    pub empty_cells: RefCell<HashSet<Pos>>,
-    pub owned_cells: RefCell<Vec<HashSet<Pos>>>,
+    pub empty_cells: RefCell<CellSet>,
+    pub owned_cells: RefCell<Vec<CellSet>>,
+    /// Masses of the cells in bit planes, on boards that fit in a bitset.
+    planes: RefCell<Option<Planes>>,
 
     pub grid: Array2D<Cell<Field>>,
-    pub capacity: Array2D<u32>,
+    capacity: Array2D<u32>,
+    /// Orbs the board holds when every cell is one short of exploding, if
+    /// cells with fewer neighbours than their capacity let explosions create
+    /// orbs. Follows the capacity map.
+    room: Option<u64>,
     player_count: Vec<Cell<u32>>,
+    history: RefCell<Vec<Move>>,
+    entered: Vec<Cell<bool>>,
+    eliminations: RefCell<Vec<Elimination>>,
+    teams: Teams,
+    rules: Rules,
+    limits: Limits,
+    stopped: Cell<Option<Outcome>>,
 }
 
 impl ChainReaction {
     pub fn new(width: usize, height: usize, players: usize) -> Result<ChainReaction, &'static str> {
-        if width == 0 {
-            Err("Width cannot be zero.")
-        } else if height == 0 {
-            Err("Height cannot be zero.")
-        } else if players == 0 {
-            Err("Player count cannot be zero.")
+        ChainReaction::with_rules(width, height, players, Rules::default())
+    }
+
+    pub fn with_rules(
+        width: usize,
+        height: usize,
+        players: usize,
+        rules: Rules,
+    ) -> Result<ChainReaction, &'static str> {
+        check_size(width, height, players)?;
+        if rules.capacity_offset > MAX_CAPACITY - 4 {
+            Err("Capacity is too large.")
         } else {
             let grid = Array2D::new(Cell::new(Field::Empty), Pos(width, height));
-            let capacity = ChainReaction::get_capacity(width, height);
-
-            let empty_cells_iter = (0..width * height).map(|p| Pos(p % width, p / width));
+            let packed = bitboard::fits(width, height);
+            let capacity = ChainReaction::get_capacity(width, height, rules.capacity_offset);
 
-            Ok(ChainReaction {
+            let mut game = ChainReaction {
                 width,
                 height,
                 players,
                 grid,
                 capacity,
+                room: None,
                 current_player: Cell::new(0),
                 move_count: Cell::new(0),
                 player_count: vec![Cell::new(0); players],
 
-                empty_cells: RefCell::new(HashSet::from_iter(empty_cells_iter)),
-                owned_cells: RefCell::new(vec![HashSet::new(); players]),
-            })
+                empty_cells: RefCell::new(CellSet::full(width, height, packed)),
+                owned_cells: RefCell::new(vec![CellSet::new(packed); players]),
+                planes: RefCell::new(Planes::new(width, height)),
+                history: RefCell::new(Vec::new()),
+                entered: vec![Cell::new(false); players],
+                eliminations: RefCell::new(Vec::new()),
+                teams: Teams::solo(players),
+                rules,
+                limits: Limits::default(),
+                stopped: Cell::new(None),
+            };
+            game.update_room();
+            Ok(game)
+        }
+    }
+
+    /// Rebuilds a game by replaying a recorded sequence of moves. Players
+    /// whose turn was skipped in the original game are skipped again.
+    pub fn from_moves(
+        width: usize,
+        height: usize,
+        players: usize,
+        moves: &[Move],
+    ) -> Result<ChainReaction, &'static str> {
+        let game = ChainReaction::new(width, height, players)?;
+        game.play_moves(moves)?;
+        Ok(game)
+    }
+
+    /// Plays a sequence of moves, skipping players until it is the turn of
+    /// the player who made each move.
+    pub fn play_moves(&self, moves: &[Move]) -> Result<(), &'static str> {
+        for m in moves {
+            self.skip_to(m.player)?;
+            self.place(&m.pos)?;
         }
+
+        Ok(())
+    }
+
+    /// Skips players until it is the turn of `player`, as happens in a
+    /// recorded game when players have no move.
+    pub fn skip_to(&self, player: usize) -> Result<(), &'static str> {
+        if player >= self.players {
+            return Err("Invalid player.");
+        }
+        while self.current_player() != player {
+            self.skip_player();
+        }
+        Ok(())
+    }
+
+    /// Sets up a game from a position. `cells` lists the fields of the board
+    /// row by row, and `move_count` is the number of moves played so far.
+    pub fn from_position(
+        width: usize,
+        height: usize,
+        players: usize,
+        rules: Rules,
+        current_player: usize,
+        move_count: u32,
+        cells: &[Field],
+    ) -> Result<ChainReaction, &'static str> {
+        let game = ChainReaction::with_rules(width, height, players, rules)?;
+        game.load_position(current_player, move_count, cells)?;
+        Ok(game)
+    }
+
+    /// Fills an empty board with the fields of a position. Split out of
+    /// `from_position` so that a custom capacity map can be applied first.
+    pub(crate) fn load_position(
+        &self,
+        current_player: usize,
+        move_count: u32,
+        cells: &[Field],
+    ) -> Result<(), &'static str> {
+        if current_player >= self.players {
+            return Err("Invalid player.");
+        }
+        if cells.len() != self.width * self.height {
+            return Err("Cell count does not match the board size.");
+        }
+
+        for (i, field) in cells.iter().enumerate() {
+            let pos = Pos(i % self.width, i / self.width);
+
+            if let Field::Owned(FieldData { owner, count }) = *field {
+                if owner >= self.players {
+                    return Err("Invalid player.");
+                }
+                if count == 0 || count >= *self.capacity.get(&pos)? {
+                    return Err("Invalid cell mass.");
+                }
+
+                self.change_owner(&pos, None, Some(owner));
+                self.increase_player_count(owner, count);
+                self.set_field(&pos, self.grid.get(&pos)?, *field);
+            }
+        }
+
+        self.current_player.set(current_player);
+        self.move_count.set(move_count);
+
+        // Without a history, assume nobody was skipped during the first
+        // round, so everyone who had a turn by now has entered the game.
+        for (player, entered) in self.entered.iter().enumerate() {
+            let owns_cells = !self.owned_cells.borrow()[player].is_empty();
+            entered.set(owns_cells || (player as u64) < u64::from(move_count));
+        }
+        self.record_eliminations(None);
+
+        Ok(())
+    }
+
+    /// Replaces the automatic capacities with a map of bespoke ones, such as
+    /// a cell that explodes on its first orb or one that holds more than it
+    /// has neighbours. Cells that already hold orbs must stay below their
+    /// new capacity.
+    pub fn set_capacity(&mut self, capacity: Array2D<u32>) -> Result<(), &'static str> {
+        if capacity.width() != self.width || capacity.height() != self.height {
+            return Err("Capacity map does not match the board size.");
+        }
+        if capacity.iter().any(|&c| c == 0) {
+            return Err("Every cell needs a capacity of at least 1.");
+        }
+        if capacity.iter().any(|&c| c > MAX_CAPACITY) {
+            return Err("Capacity is too large.");
+        }
+        for (field, &c) in self.grid.iter().zip(capacity.iter()) {
+            if let Field::Owned(FieldData { count, .. }) = field.get() {
+                if count >= c {
+                    return Err("Invalid cell mass.");
+                }
+            }
+        }
+
+        self.capacity = capacity;
+        self.update_room();
+        Ok(())
+    }
+
+    pub fn capacity(&self) -> &Array2D<u32> {
+        &self.capacity
+    }
+
+    /// The capacity map if it differs from the one derived from the board
+    /// size and rules.
+    pub fn custom_capacity(&self) -> Option<&Array2D<u32>> {
+        let automatic =
+            ChainReaction::get_capacity(self.width, self.height, self.rules.capacity_offset);
+        Some(&self.capacity).filter(|&capacity| *capacity != automatic)
     }
 
     pub fn width(&self) -> usize {
@@ -86,42 +517,138 @@ impl ChainReaction {
         self.current_player.get()
     }
 
+    pub fn players(&self) -> usize {
+        self.players
+    }
+
+    /// Total mass of all cells owned by `player`.
+    pub fn player_mass(&self, player: usize) -> u32 {
+        self.player_count.get(player).map_or(0, Cell::get)
+    }
+
+    pub fn move_count(&self) -> u32 {
+        self.move_count.get()
+    }
+
+    pub fn history(&self) -> Ref<'_, Vec<Move>> {
+        self.history.borrow()
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub fn teams(&self) -> &Teams {
+        &self.teams
+    }
+
+    pub fn set_teams(&mut self, teams: Teams) -> Result<(), &'static str> {
+        teams.validate(self.players)?;
+        self.teams = teams;
+        Ok(())
+    }
+
+    pub fn team(&self, player: usize) -> usize {
+        self.teams.assignment[player]
+    }
+
+    fn allied(&self, a: usize, b: usize) -> bool {
+        self.team(a) == self.team(b)
+    }
+
+    /// Whether `player` is still in the game. Players who have not placed
+    /// yet are only out if the board filled up before their first turn.
+    /// Mass is used rather than cells so that a player whose only cell is
+    /// exploding is not counted as eliminated while its orbs are in flight.
     pub fn player_is_alive(&self, player: usize) -> bool {
-        self.move_count.get() < self.players.try_into().unwrap()
-            || self.owned_cells.borrow().get(player).unwrap().len() > 0
+        match self.entered.get(player) {
+            Some(entered) if entered.get() => self.player_count[player].get() > 0,
+            Some(_) => !self.is_eliminated(player),
+            None => false,
+        }
     }
 
-    fn change_owner(&self, pos: &Pos, prev_owner: Option<usize>, new_owner: Option<usize>) {
-        match (prev_owner, new_owner) {
-            (None, Some(new)) => {
-                self.empty_cells.borrow_mut().remove(pos);
-                self.owned_cells
-                    .borrow_mut()
-                    .get_mut(new)
-                    .map(|x| x.insert(*pos));
-            }
-            (Some(old), None) => {
-                self.owned_cells
-                    .borrow_mut()
-                    .get_mut(old)
-                    .map(|x| x.remove(pos));
-                self.empty_cells.borrow_mut().insert(*pos);
-            }
-            (Some(old), Some(new)) => {
-                self.owned_cells
-                    .borrow_mut()
-                    .get_mut(old)
-                    .map(|x| x.remove(pos));
-                self.owned_cells
-                    .borrow_mut()
-                    .get_mut(new)
-                    .map(|x| x.insert(*pos));
+    fn is_eliminated(&self, player: usize) -> bool {
+        self.eliminations
+            .borrow()
+            .iter()
+            .any(|elimination| elimination.player == player)
+    }
+
+    pub fn eliminations(&self) -> Ref<'_, Vec<Elimination>> {
+        self.eliminations.borrow()
+    }
+
+    /// Logs every player who dropped out of the game since the last call.
+    fn record_eliminations(&self, by: Option<usize>) {
+        let board_full = self.empty_cells.borrow().is_empty();
+
+        for player in 0..self.players {
+            let out = if self.entered[player].get() {
+                self.player_count[player].get() == 0
+            } else {
+                board_full
+            };
+
+            if out && !self.is_eliminated(player) {
+                self.eliminations.borrow_mut().push(Elimination {
+                    player,
+                    ply: self.move_count.get(),
+                    by,
+                });
             }
-            (None, None) => {}
-        };
+        }
+    }
+
+    fn change_owner(&self, pos: &Pos, prev_owner: Option<usize>, new_owner: Option<usize>) {
+        match prev_owner {
+            Some(owner) => self.owned_cells.borrow_mut()[owner].remove(pos),
+            None => self.empty_cells.borrow_mut().remove(pos),
+        }
+        match new_owner {
+            Some(owner) => self.owned_cells.borrow_mut()[owner].insert(*pos),
+            None => self.empty_cells.borrow_mut().insert(*pos),
+        }
+    }
+
+    /// Sets a field of the board, keeping the mass planes up to date.
+    fn set_field(&self, pos: &Pos, field: &Cell<Field>, value: Field) {
+        field.set(value);
+        if let Some(planes) = self.planes.borrow_mut().as_mut() {
+            let mass = match value {
+                Field::Empty => 0,
+                Field::Owned(FieldData { count, .. }) => count,
+            };
+            planes.set_mass(pos, mass);
+        }
     }
 
     pub fn place(&self, pos: &Pos) -> Result<(), &'static str> {
+        self.place_traced(pos).map(|_| ())
+    }
+
+    /// Same as `place`, but also reports which cells exploded and which were
+    /// captured along the way.
+    pub fn place_traced(&self, pos: &Pos) -> Result<Explosion, &'static str> {
+        self.place_stepwise(pos, &mut |_, _| {})
+    }
+
+    /// Same as `place_traced`, but also calls `on_wave` after every wave of
+    /// the explosion has been resolved, with the board in its intermediate
+    /// state and the cells that exploded in that wave.
+    pub fn place_stepwise(
+        &self,
+        pos: &Pos,
+        on_wave: &mut dyn FnMut(&ChainReaction, &[Pos]),
+    ) -> Result<Explosion, &'static str> {
         let player = self.current_player.get();
         if !self.active() {
             return Err("Cannot play after the game is finished.");
@@ -130,20 +657,29 @@ impl ChainReaction {
         if player >= self.players {
             return Err("Invalid player.");
         }
+        if !self.player_is_alive(player) {
+            return Err("Player has been eliminated.");
+        }
 
         let field = self.grid.get(pos)?;
+        if !self.can_play(pos)? {
+            return Err("Field is already taken.");
+        }
+
+        let mut explosion = Explosion::default();
 
         let new_data = match field.get() {
             Field::Owned(FieldData { owner, count }) => {
                 if owner != player {
-                    return Err("Field is already taken.");
-                } else {
-                    self.change_owner(pos, Some(owner), Some(player));
+                    self.decrease_player_count(owner, count);
+                    self.increase_player_count(player, count);
+                    explosion.captured.push(*pos);
+                }
+                self.change_owner(pos, Some(owner), Some(player));
 
-                    FieldData {
-                        owner: player,
-                        count: count + 1,
-                    }
+                FieldData {
+                    owner: player,
+                    count: count + 1,
                 }
             }
             Field::Empty => {
@@ -157,89 +693,192 @@ impl ChainReaction {
         };
 
         let &capacity = self.capacity.get(pos)?;
+        self.entered[player].set(true);
         self.increase_player_count(player, 1);
-        self.move_count.set(self.move_count.get() + 1);
+        self.move_count.set(self.move_count.get().saturating_add(1));
+        self.history.borrow_mut().push(Move { player, pos: *pos });
 
         if new_data.count >= capacity {
-            field.set(Field::Empty);
-            self.change_owner(pos, Some(player), None);
+            let orbs = self.burst(pos, field, new_data);
 
-            self.explode(pos, player)?;
+            explosion.waves.push(vec![*pos]);
+            on_wave(self, &[*pos]);
+            self.explode(orbs, player, &mut explosion, on_wave)?;
         } else {
-            field.set(Field::Owned(new_data));
+            self.set_field(pos, field, Field::Owned(new_data));
         }
 
         self.current_player.set((player + 1) % self.players);
+        self.record_eliminations(Some(player));
 
-        Ok(())
+        if let Some(max_plies) = self.limits.max_plies {
+            if self.active() && self.move_count.get() >= max_plies {
+                self.stopped.set(Some(Outcome::Aborted));
+            }
+        }
+
+        Ok(explosion)
     }
 
-    pub fn skip_player(&self) -> () {
+    pub fn skip_player(&self) {
         self.current_player
             .set((self.current_player.get() + 1) % self.players);
     }
 
     pub fn can_play(&self, pos: &Pos) -> Result<bool, &'static str> {
         let field = self.grid.get(pos)?.get();
+        let player = self.current_player.get();
 
         let result = match field {
             Field::Empty => true,
-            Field::Owned(FieldData { owner, .. }) => owner == self.current_player.get(),
+            Field::Owned(FieldData { owner, .. }) if owner == player => true,
+            Field::Owned(FieldData { owner, .. }) => {
+                self.rules.place_on_opponents
+                    && (!self.allied(owner, player) || self.teams.convert_allies)
+            }
         };
 
         Ok(result)
     }
 
-    fn get_capacity(width: usize, height: usize) -> Array2D<u32> {
-        let mut grid = Array2D::new(4, Pos(width, height));
+    /// Cells the current player may place on, ordered by column and then by
+    /// row. Nothing is allocated on boards of up to 16x16 cells, so this is
+    /// cheap to call every turn.
+    pub fn legal_moves(&self) -> impl Iterator<Item = Pos> + '_ {
+        match self.legal_bits() {
+            Some(legal) => LegalMoves::Packed(Cells::new(&legal)),
+            None => LegalMoves::Scanned(Box::new(
+                (0..self.width)
+                    .flat_map(move |x| (0..self.height).map(move |y| Pos(x, y)))
+                    .filter(move |pos| self.can_play(pos) == Ok(true)),
+            )),
+        }
+    }
+
+    /// The cells `legal_moves` lists, unless the cell sets are kept in hash
+    /// sets.
+    fn legal_bits(&self) -> Option<Bits> {
+        let player = self.current_player.get();
+        let owned = self.owned_cells.borrow();
+        let mut legal = self
+            .empty_cells
+            .borrow()
+            .bits()?
+            .union(owned[player].bits()?);
+
+        if self.rules.place_on_opponents {
+            for (owner, cells) in owned.iter().enumerate() {
+                if owner != player && (!self.allied(owner, player) || self.teams.convert_allies) {
+                    legal = legal.union(cells.bits()?);
+                }
+            }
+        }
+
+        Some(legal)
+    }
+
+    /// Replaces the contents of `moves` with the legal moves, reusing its
+    /// allocation.
+    pub fn legal_moves_into(&self, moves: &mut Vec<Pos>) {
+        moves.clear();
+        moves.extend(self.legal_moves());
+    }
+
+    pub fn legal_move_count(&self) -> usize {
+        match self.legal_bits() {
+            Some(legal) => legal.len(),
+            None if self.rules.place_on_opponents => self.legal_moves().count(),
+            None => {
+                self.empty_cells.borrow().len()
+                    + self.owned_cells.borrow()[self.current_player.get()].len()
+            }
+        }
+    }
+
+    /// Capacity of every cell: its number of neighbours plus `offset`, and at
+    /// least 1, as custom capacities must be.
+    fn get_capacity(width: usize, height: usize, offset: u32) -> Array2D<u32> {
+        let mut grid = Array2D::new(4 + offset, Pos(width, height));
 
         for y in 0..height {
             if let Ok(f) = grid.get_mut(&Pos(0, y)) {
-                *f -= 1;
+                *f = (*f - 1).max(1);
             }
 
             if let Ok(f) = grid.get_mut(&Pos(width - 1, y)) {
-                *f -= 1;
+                *f = (*f - 1).max(1);
             }
         }
 
         for x in 0..width {
             if let Ok(f) = grid.get_mut(&Pos(x, 0)) {
-                *f -= 1;
+                *f = (*f - 1).max(1);
             }
 
             if let Ok(f) = grid.get_mut(&Pos(x, height - 1)) {
-                *f -= 1;
+                *f = (*f - 1).max(1);
             }
         }
 
         grid
     }
 
-    pub fn neighbors(&self, pos: &Pos) -> Vec<(Pos, &Cell<Field>)> {
+    pub fn neighbors(&self, pos: &Pos) -> Neighbors<'_> {
         let Pos(x, y) = *pos;
 
-        let offsets = (0..4).map(|i| match i {
-            0 => x.checked_sub(1).map(|x| Pos(x, y)),
-            1 => Some(Pos(x + 1, y)),
-            2 => y.checked_sub(1).map(|y| Pos(x, y)),
-            3 => Some(Pos(x, y + 1)),
-            _ => unreachable!(),
-        });
+        let offsets = [
+            x.checked_sub(1).map(|x| Pos(x, y)),
+            x.checked_add(1).map(|x| Pos(x, y)),
+            y.checked_sub(1).map(|y| Pos(x, y)),
+            y.checked_add(1).map(|y| Pos(x, y)),
+        ];
 
-        let positions: Vec<_> = offsets.flatten().filter(|x| self.in_bounds(x)).collect();
+        let mut neighbors = Neighbors {
+            grid: &self.grid,
+            cells: [Pos(0, 0); 4],
+            front: 0,
+            back: 0,
+        };
+        for &pos in offsets.iter().flatten() {
+            if pos.0 < self.width && pos.1 < self.height {
+                neighbors.cells[neighbors.back] = pos;
+                neighbors.back += 1;
+            }
+        }
+        neighbors
+    }
 
-        let cells: Vec<_> = positions
-            .iter()
-            .map(|pos| self.grid.get(pos))
-            .flatten()
-            .collect();
+    /// Orbs on the cells within `radius` steps of `pos`, not counting `pos`
+    /// itself.
+    pub fn mass_within(&self, pos: &Pos, radius: usize) -> Result<u32, &'static str> {
+        self.grid.get(pos)?;
+        if let Some(planes) = self.planes.borrow().as_ref() {
+            return Ok(planes.mass_within(pos, radius));
+        }
 
-        positions.iter().cloned().zip(cells).collect()
+        let Pos(x, y) = *pos;
+        let mut sum = 0;
+        let right = x.saturating_add(radius).min(self.width - 1);
+        for nx in x.saturating_sub(radius)..=right {
+            let reach = radius - nx.abs_diff(x);
+            let bottom = y.saturating_add(reach).min(self.height - 1);
+            for ny in y.saturating_sub(reach)..=bottom {
+                if let Field::Owned(FieldData { count, .. }) = self.grid.get(&Pos(nx, ny))?.get() {
+                    if (nx, ny) != (x, y) {
+                        sum += count;
+                    }
+                }
+            }
+        }
+        Ok(sum)
     }
 
-    fn in_bounds(&self, &Pos(x, y): &Pos) -> bool {
-        x < self.width && y < self.height
+    /// Whether any enemy of `player` still has mass on the board.
+    fn contested(&self, player: usize) -> bool {
+        self.player_count
+            .iter()
+            .enumerate()
+            .any(|(other, count)| !self.allied(other, player) && count.get() > 0)
     }
 
     fn increase_player_count(&self, player: usize, delta: u32) {
@@ -251,96 +890,695 @@ impl ChainReaction {
     }
 
     pub fn active(&self) -> bool {
-        let alive_players_count: u32 = self
-            .player_count
-            .iter()
-            .filter(|x| x.get() > 0)
-            .count()
-            .try_into()
-            .unwrap();
+        if self.stopped.get().is_some() {
+            return false;
+        }
 
-        if self.move_count.get() <= alive_players_count {
-            true
-        } else {
-            assert!(alive_players_count > 0);
-            alive_players_count > 1
+        let mut teams = (0..self.players)
+            .filter(|&player| self.player_is_alive(player))
+            .map(|player| self.team(player));
+        let first = teams.next();
+        teams.any(|team| Some(team) != first)
+    }
+
+    /// How the game ended, or `None` while it is still in progress.
+    pub fn outcome(&self) -> Option<Outcome> {
+        if let Some(outcome) = self.stopped.get() {
+            return Some(outcome);
         }
+        if self.active() {
+            return None;
+        }
+
+        (0..self.players)
+            .find(|&player| self.player_is_alive(player))
+            .map(Outcome::Win)
+    }
+
+    /// The result of a finished game together with its final standings, or
+    /// `None` while the game is still in progress.
+    pub fn game_outcome(&self) -> Option<GameOutcome> {
+        let result = self.outcome()?;
+        let eliminations = self.eliminations.borrow().clone();
+
+        let mut ranking: Vec<_> = (0..self.players)
+            .filter(|&player| !self.is_eliminated(player))
+            .collect();
+        ranking.sort_by_key(|&player| std::cmp::Reverse(self.player_mass(player)));
+        ranking.extend(
+            eliminations
+                .iter()
+                .rev()
+                .map(|elimination| elimination.player),
+        );
+
+        Some(GameOutcome {
+            result,
+            ranking,
+            eliminations,
+        })
     }
 
     pub fn winner(&self) -> Result<usize, &'static str> {
-        if self.active() {
-            Err("Game is still in progress.")
+        match self.outcome() {
+            Some(Outcome::Win(winner)) => Ok(winner),
+            Some(_) => Err("There is no winner."),
+            None => Err("Game is still in progress."),
+        }
+    }
+
+    /// Adds an orb owned by `orb_owner` to a cell and returns what the cell
+    /// would hold. The caller decides whether the cell explodes.
+    fn receive_orb(
+        &self,
+        pos: &Pos,
+        field: &Cell<Field>,
+        orb_owner: usize,
+        explosion: &mut Explosion,
+    ) -> FieldData {
+        match field.get() {
+            Field::Owned(data) if data.owner == orb_owner => FieldData {
+                owner: orb_owner,
+                count: data.count + 1,
+            },
+            Field::Owned(data)
+                if self.allied(data.owner, orb_owner) && !self.teams.convert_allies =>
+            {
+                self.decrease_player_count(orb_owner, 1);
+                self.increase_player_count(data.owner, 1);
+
+                FieldData {
+                    owner: data.owner,
+                    count: data.count + 1,
+                }
+            }
+            Field::Owned(data) => {
+                self.change_owner(pos, Some(data.owner), Some(orb_owner));
+                self.increase_player_count(orb_owner, data.count);
+                self.decrease_player_count(data.owner, data.count);
+                explosion.captured.push(*pos);
+
+                FieldData {
+                    owner: orb_owner,
+                    count: data.count + 1,
+                }
+            }
+            Field::Empty => {
+                self.change_owner(pos, None, Some(orb_owner));
+
+                FieldData {
+                    owner: orb_owner,
+                    count: 1,
+                }
+            }
+        }
+    }
+
+    /// Explodes a cell holding `data` and returns the orbs it sends out, one
+    /// per neighbour. Orbs in flight still count towards their owner's mass.
+    fn burst<'a>(
+        &'a self,
+        pos: &Pos,
+        field: &Cell<Field>,
+        data: FieldData,
+    ) -> impl DoubleEndedIterator<Item = (Pos, &'a Cell<Field>, usize)> + 'a {
+        let neighbors = self.neighbors(pos);
+        let sent = neighbors.len() as u32;
+        // A cell without neighbours would keep all of its orbs and stay at
+        // capacity, so they are lost as under the classic rules.
+        let kept = if self.rules.keep_excess && sent > 0 {
+            data.count.saturating_sub(sent)
         } else {
-            self.player_count
-                .iter()
-                .position(|x| x.get() > 0)
-                .ok_or("There is no winner.")
+            0
+        };
+
+        if kept > 0 {
+            let kept = FieldData {
+                owner: data.owner,
+                count: kept,
+            };
+            self.set_field(pos, field, Field::Owned(kept));
+        } else {
+            self.set_field(pos, field, Field::Empty);
+            self.change_owner(pos, Some(data.owner), None);
         }
+
+        // The mass only changes when the cell holds a different number of
+        // orbs than it has neighbours.
+        let after = sent + kept;
+        if after > data.count {
+            self.increase_player_count(data.owner, after - data.count);
+        } else {
+            self.decrease_player_count(data.owner, data.count - after);
+        }
+
+        neighbors.map(move |(pos, field)| (pos, field, data.owner))
     }
 
-    fn explode(&self, origin: &Pos, player: usize) -> Result<(), &'static str> {
-        let mut queue = self.neighbors(origin);
+    fn explode<'a>(
+        &'a self,
+        orbs: impl DoubleEndedIterator<Item = (Pos, &'a Cell<Field>, usize)>,
+        player: usize,
+        explosion: &mut Explosion,
+        on_wave: &mut dyn FnMut(&ChainReaction, &[Pos]),
+    ) -> Result<(), &'static str> {
+        let mut runaway = Runaway::new(self.room);
 
-        let mut increase_count = 0;
+        match self.rules.resolution {
+            Resolution::BreadthFirst => {
+                let mut queue: Vec<_> = orbs.collect();
 
-        while queue.len() > 0 {
-            let mut new_queue: Vec<(Pos, &Cell<Field>)> = Vec::new();
+                while !queue.is_empty() {
+                    let mut new_queue = Vec::new();
+                    let mut wave = Vec::new();
 
-            for (pos, field) in &queue {
-                let new_data = match field.get() {
-                    Field::Owned(data) => {
-                        if data.owner != player {
-                            self.change_owner(pos, Some(data.owner), Some(player));
-                            increase_count += data.count;
-                            self.decrease_player_count(data.owner, data.count);
+                    for (pos, field, orb_owner) in queue {
+                        let data = self.receive_orb(&pos, field, orb_owner, explosion);
+                        if data.count >= *self.capacity.get(&pos)? {
+                            wave.push(pos);
+                            new_queue.extend(self.burst(&pos, field, data));
+                        } else {
+                            self.set_field(&pos, field, Field::Owned(data));
                         }
+                    }
 
-                        FieldData {
-                            owner: player,
-                            count: data.count + 1,
-                        }
+                    if self.finish_wave(player, wave, &new_queue, explosion, on_wave, &mut runaway)
+                    {
+                        queue = new_queue;
+                    } else {
+                        queue = vec![];
                     }
-                    Field::Empty => {
-                        self.change_owner(pos, None, Some(player));
+                }
+            }
+            Resolution::DepthFirst => {
+                // Reversed so that orbs are handed out in neighbour order.
+                let mut stack: Vec<_> = orbs.rev().collect();
 
-                        FieldData {
-                            owner: player,
-                            count: 1,
-                        }
+                while let Some((pos, field, orb_owner)) = stack.pop() {
+                    let data = self.receive_orb(&pos, field, orb_owner, explosion);
+                    if data.count < *self.capacity.get(&pos)? {
+                        self.set_field(&pos, field, Field::Owned(data));
+                        continue;
                     }
-                };
-                let &capacity = self.capacity.get(pos)?;
-                if new_data.count >= capacity {
-                    field.set(Field::Empty);
-                    self.change_owner(pos, Some(player), None);
-
-                    for (neighbor_pos, neighbor) in self.neighbors(pos) {
-                        new_queue.push((neighbor_pos, neighbor));
+
+                    stack.extend(self.burst(&pos, field, data).rev());
+                    if !self.finish_wave(
+                        player,
+                        vec![pos],
+                        &stack,
+                        explosion,
+                        on_wave,
+                        &mut runaway,
+                    ) {
+                        break;
                     }
-                } else {
-                    field.set(Field::Owned(new_data));
                 }
             }
+        }
 
+        Ok(())
+    }
+
+    /// Works out `room` for the current capacity map.
+    fn update_room(&mut self) {
+        let mut creates_orbs = false;
+        let mut room = 0;
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let pos = Pos(x, y);
+                let capacity = self.capacity.get(&pos).map_or(1, |&c| c);
+                creates_orbs |= (capacity as usize) < self.neighbors(&pos).len();
+                room += u64::from(capacity.saturating_sub(1));
+            }
+        }
+
+        self.room = Some(room).filter(|_| creates_orbs);
+    }
+
+    /// Mass of all players together, which includes the orbs in flight.
+    fn total_mass(&self) -> u64 {
+        self.player_count.iter().map(|c| u64::from(c.get())).sum()
+    }
+
+    /// Records a wave of an explosion started by `player` and decides
+    /// whether the orbs in `pending` should still be handed out.
+    fn finish_wave(
+        &self,
+        player: usize,
+        wave: Vec<Pos>,
+        pending: &[(Pos, &Cell<Field>, usize)],
+        explosion: &mut Explosion,
+        on_wave: &mut dyn FnMut(&ChainReaction, &[Pos]),
+        runaway: &mut Runaway<ExplosionState>,
+    ) -> bool {
+        if !wave.is_empty() {
+            on_wave(self, &wave);
+            let max_waves = self.limits.max_waves.map(|max| max as usize);
+            if max_waves.is_some_and(|max| explosion.waves.len() >= max) && self.active() {
+                // Orbs still in flight stay counted towards their owners,
+                // as with any explosion that ends the game.
+                self.stopped.set(Some(Outcome::Draw));
+            }
+            explosion.waves.push(wave);
+        }
+
+        let contested = self.contested(player);
+        let mass = self.total_mass();
+        if contested && self.rules.resolution == Resolution::DepthFirst && runaway.overflowing(mass)
+        {
+            // Followed depth first, an explosion can keep feeding the same
+            // few cells without ever reaching an enemy, so once it cannot
+            // settle anymore the game ends like it does at the wave limit.
             if self.active() {
-                queue = new_queue;
-            } else {
-                queue = vec![];
+                self.stopped.set(Some(Outcome::Draw));
             }
+            return false;
         }
 
-        self.increase_player_count(player, increase_count);
+        // Once no enemy is left on the board, the orbs can only circulate
+        // among the team's own cells. That may go on forever while players
+        // who have yet to move keep the game going, so stop once the
+        // explosion is found to repeat itself. The orbs in flight are lost
+        // since the game goes on.
+        let cycling = !contested
+            && (runaway.overflowing(mass)
+                || runaway.repeats(pending.len(), mass, || {
+                    let board: Vec<_> = self.grid.iter().map(Cell::get).collect();
+                    let pending: Vec<_> = pending
+                        .iter()
+                        .map(|&(pos, _, owner)| (pos, owner))
+                        .collect();
+                    (board, pending)
+                }));
+        if cycling && self.active() {
+            for &(_, _, owner) in pending {
+                self.decrease_player_count(owner, 1);
+            }
+            explosion.dropped = pending.len();
+        }
 
-        Ok(())
+        self.active() && !cycling
     }
 }
 
+/// Cells next to a cell along with their fields, listed left, right, up and
+/// down. Only cells on the board are included.
+pub struct Neighbors<'a> {
+    grid: &'a Array2D<Cell<Field>>,
+    cells: [Pos; 4],
+    front: usize,
+    back: usize,
+}
+
+impl<'a> Iterator for Neighbors<'a> {
+    type Item = (Pos, &'a Cell<Field>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        let pos = self.cells[self.front];
+        self.front += 1;
+        Some((pos, self.grid.get(&pos).ok()?))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for Neighbors<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        let pos = self.cells[self.back];
+        Some((pos, self.grid.get(&pos).ok()?))
+    }
+}
+
+impl ExactSizeIterator for Neighbors<'_> {}
+
 impl fmt::Display for ChainReaction {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.grid.fmt(f)?;
-        write!(f, "\n")?;
+        writeln!(f)?;
         let values: Vec<_> = self.player_count.iter().map(|x| x.get()).collect();
         write!(f, "{:?}", values)?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notation::{format_position, parse_position};
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn limits_end_games_without_a_winner() {
+        let mut game = parse_position("position 3 3 2 0 4\n1:0 2:0 .\n. . .\n. . 1:1").unwrap();
+        game.set_limits(Limits {
+            max_waves: Some(1),
+            max_plies: None,
+        });
+        let explosion = game.place_traced(&Pos(0, 0)).unwrap();
+        assert_eq!(explosion.waves.len(), 2);
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+        assert!(game.winner().is_err());
+
+        let mut game = ChainReaction::new(3, 3, 2).unwrap();
+        game.set_limits(Limits {
+            max_waves: None,
+            max_plies: Some(2),
+        });
+        game.place(&Pos(0, 0)).unwrap();
+        assert_eq!(game.outcome(), None);
+        game.place(&Pos(2, 2)).unwrap();
+        assert_eq!(game.outcome(), Some(Outcome::Aborted));
+        assert!(game.place(&Pos(0, 0)).is_err());
+    }
+
+    #[test]
+    fn ranks_players_by_elimination() {
+        let game = parse_position("position 3 3 3 0 6\n1:0 2:1 1:2\n. . .\n. . .").unwrap();
+        assert!(game.eliminations().is_empty());
+        assert!(game.game_outcome().is_none());
+
+        game.place(&Pos(0, 0)).unwrap();
+        let eliminated = |player| Elimination {
+            player,
+            ply: 7,
+            by: Some(0),
+        };
+        assert_eq!(
+            game.game_outcome(),
+            Some(GameOutcome {
+                result: Outcome::Win(0),
+                ranking: vec![0, 2, 1],
+                eliminations: vec![eliminated(1), eliminated(2)],
+            })
+        );
+    }
+
+    #[test]
+    fn eliminated_players_cannot_place() {
+        let position = "position 3 3 4 0 8\n1:0 2:1 .\n. . .\n. 1:2 1:3";
+        let game = parse_position(position).unwrap();
+        game.place(&Pos(0, 0)).unwrap();
+        assert!(!game.player_is_alive(1));
+        assert_eq!(game.current_player(), 1);
+        assert!(game.place(&Pos(0, 2)).is_err());
+        assert!(!game.player_is_alive(1));
+
+        let game = parse_position(position).unwrap();
+        let moves = [
+            Move {
+                player: 0,
+                pos: Pos(0, 0),
+            },
+            Move {
+                player: 1,
+                pos: Pos(0, 2),
+            },
+        ];
+        assert!(game.play_moves(&moves).is_err());
+        assert_eq!(game.eliminations().len(), 1);
+    }
+
+    #[test]
+    fn rule_variants() {
+        let position = "position 3 3 2 0 2\nrules 1 keep bfs any\n2:0 . .\n. . .\n. . 1:1";
+        let game = parse_position(position).unwrap();
+        assert_eq!(format_position(&game), position);
+
+        // A corner holds three orbs before exploding and keeps the one it
+        // cannot send out.
+        game.place(&Pos(0, 0)).unwrap();
+        let owned = |owner, count| Field::Owned(FieldData { owner, count });
+        assert_eq!(game.grid.get(&Pos(0, 0)).unwrap().get(), owned(0, 1));
+        assert_eq!(game.grid.get(&Pos(1, 0)).unwrap().get(), owned(0, 1));
+        assert_eq!(game.player_mass(0), 3);
+
+        let explosion = game.place_traced(&Pos(0, 0)).unwrap();
+        assert_eq!(explosion.captured, vec![Pos(0, 0)]);
+        assert_eq!(game.grid.get(&Pos(0, 0)).unwrap().get(), owned(1, 2));
+        assert_eq!((game.player_mass(0), game.player_mass(1)), (2, 3));
+
+        let play = |resolution| {
+            let game = parse_position(&format!(
+                "position 3 3 2 0 4\nrules 0 discard {} own\n1:0 2:0 .\n2:0 . .\n. . 1:1",
+                resolution
+            ))
+            .unwrap();
+            let explosion = game.place_traced(&Pos(0, 0)).unwrap();
+            let cells: Vec<_> = game.grid.iter().map(Cell::get).collect();
+            (explosion.waves, cells)
+        };
+
+        let (bfs, bfs_cells) = play("bfs");
+        let (dfs, dfs_cells) = play("dfs");
+        assert_eq!(bfs[1].len(), 2);
+        assert!(dfs.len() > bfs.len() && dfs.iter().all(|wave| wave.len() == 1));
+        assert_eq!(bfs_cells, dfs_cells);
+    }
+
+    #[test]
+    fn legal_moves() {
+        let rules = Rules {
+            place_on_opponents: true,
+            ..Rules::default()
+        };
+        let game = ChainReaction::new(3, 2, 2).unwrap();
+        let open = ChainReaction::with_rules(3, 2, 2, rules).unwrap();
+        game.place(&Pos(0, 0)).unwrap();
+        open.place(&Pos(0, 0)).unwrap();
+
+        let mut moves = vec![Pos(0, 0)];
+        game.legal_moves_into(&mut moves);
+        assert_eq!(
+            moves,
+            [Pos(0, 1), Pos(1, 0), Pos(1, 1), Pos(2, 0), Pos(2, 1)]
+        );
+        assert_eq!(game.legal_move_count(), 5);
+        assert_eq!(open.legal_moves().next(), Some(Pos(0, 0)));
+        assert_eq!(open.legal_move_count(), 6);
+    }
+
+    #[test]
+    fn custom_capacity() {
+        let mut game = ChainReaction::new(3, 3, 3).unwrap();
+        let mut capacity = game.capacity().clone();
+        *capacity.get_mut(&Pos(1, 1)).unwrap() = 1;
+        *capacity.get_mut(&Pos(0, 0)).unwrap() = 6;
+        assert!(game.set_capacity(Array2D::new(2, Pos(3, 2))).is_err());
+        assert!(game.set_capacity(Array2D::new(0, Pos(3, 3))).is_err());
+        game.set_capacity(capacity).unwrap();
+
+        // The hot centre explodes as soon as it is played.
+        let explosion = game.place_traced(&Pos(1, 1)).unwrap();
+        assert_eq!(explosion.waves, vec![vec![Pos(1, 1)]]);
+        assert_eq!(game.player_mass(0), 4);
+
+        let text = format_position(&game);
+        assert!(text.contains("\ncapacity\n6 3 2\n3 1 3\n2 3 2\n"));
+        assert_eq!(format_position(&parse_position(&text).unwrap()), text);
+
+        // Hot cells create orbs, so an explosion over a board of them never
+        // settles. It has to stop even while the other players have yet to
+        // move.
+        let mut game = ChainReaction::new(2, 2, 3).unwrap();
+        game.set_capacity(Array2D::new(1, Pos(2, 2))).unwrap();
+        game.place(&Pos(0, 0)).unwrap();
+        assert!(game.active());
+    }
+
+    #[test]
+    fn contested_depth_first_overflow_is_a_draw() {
+        // Followed depth first, the orbs bounce between the two hot cells at
+        // the bottom and never reach the enemy, so the game ends in a draw.
+        let position = "position 3 3 2 1 1\nrules 0 keep dfs any\ncapacity\n2 1 1\n1 2 1\n1 1 1\n1:0 . .\n. . .\n. . .";
+        let game = parse_position(position).unwrap();
+        game.place(&Pos(2, 2)).unwrap();
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+    }
+
+    #[test]
+    fn long_runaway_explosions_stop() {
+        // Two hot cells side by side feed each other and pump orbs into the
+        // rest of the board for thousands of waves before it overflows, so
+        // watching the explosion must not cost a pass over the board every
+        // wave.
+        let rules = Rules {
+            resolution: Resolution::DepthFirst,
+            ..Rules::default()
+        };
+        let mut game = ChainReaction::with_rules(64, 64, 2, rules).unwrap();
+        let mut capacity = game.capacity().clone();
+        *capacity.get_mut(&Pos(0, 0)).unwrap() = 1;
+        *capacity.get_mut(&Pos(1, 0)).unwrap() = 1;
+        game.set_capacity(capacity).unwrap();
+
+        let explosion = game.place_traced(&Pos(0, 0)).unwrap();
+        assert!(explosion.waves.len() > 1000);
+        assert!(explosion.dropped > 0);
+    }
+
+    #[test]
+    fn explosions_spare_allies() {
+        let position = "position 3 3 4 0 8\n1:0 1:2 .\n. . .\n. . 1:3";
+        let teams = |convert_allies| Teams {
+            assignment: vec![0, 1, 0, 1],
+            convert_allies,
+        };
+
+        let mut game = parse_position(position).unwrap();
+        game.set_teams(teams(false)).unwrap();
+        let explosion = game.place_traced(&Pos(0, 0)).unwrap();
+        assert!(explosion.captured.is_empty());
+        assert_eq!(
+            game.grid.get(&Pos(1, 0)).unwrap().get(),
+            Field::Owned(FieldData { owner: 2, count: 2 })
+        );
+        assert_eq!((game.player_mass(0), game.player_mass(2)), (1, 2));
+
+        let mut game = parse_position(position).unwrap();
+        game.set_teams(teams(true)).unwrap();
+        let explosion = game.place_traced(&Pos(0, 0)).unwrap();
+        assert_eq!(explosion.captured, vec![Pos(1, 0)]);
+        assert!(!game.player_is_alive(2) && game.active());
+
+        let mut game = parse_position("position 3 3 4 0 8\n1:0 1:3 .\n. . .\n. . 1:2").unwrap();
+        game.set_teams(teams(false)).unwrap();
+        assert!(game.active());
+        game.place(&Pos(0, 0)).unwrap();
+        assert_eq!(game.outcome(), Some(Outcome::Win(0)));
+    }
+
+    fn cells(set: &CellSet) -> BTreeSet<Pos> {
+        set.iter().collect()
+    }
+
+    /// Plays random games with the cells kept in bitsets and in hash sets,
+    /// comparing the two after every move.
+    fn compare_storage(rules: Rules, teams: Option<Teams>, capacity_map: bool, seed: u64) {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+        for _ in 0..30 {
+            let width = rng.gen_range(1..=6);
+            let height = rng.gen_range(1..=6);
+            let players = teams
+                .as_ref()
+                .map_or(rng.gen_range(2..=4), |t| t.assignment.len());
+
+            let mut packed = ChainReaction::with_rules(width, height, players, rules).unwrap();
+            let mut hashed = packed.clone();
+            hashed.empty_cells = RefCell::new(CellSet::full(width, height, false));
+            hashed.owned_cells = RefCell::new(vec![CellSet::new(false); players]);
+            hashed.planes = RefCell::new(None);
+            assert!(packed.planes.borrow().is_some());
+
+            for game in [&mut packed, &mut hashed] {
+                game.set_limits(Limits {
+                    max_waves: Some(200),
+                    max_plies: Some(300),
+                });
+                if let Some(teams) = &teams {
+                    game.set_teams(teams.clone()).unwrap();
+                }
+            }
+            if capacity_map {
+                let mut capacity = Array2D::new(0, Pos(width, height));
+                for y in 0..height {
+                    for x in 0..width {
+                        *capacity.get_mut(&Pos(x, y)).unwrap() = rng.gen_range(1..=5);
+                    }
+                }
+                packed.set_capacity(capacity.clone()).unwrap();
+                hashed.set_capacity(capacity).unwrap();
+            }
+
+            while packed.active() {
+                let moves: Vec<_> = packed.legal_moves().collect();
+                if !packed.player_is_alive(packed.current_player()) || moves.is_empty() {
+                    packed.skip_player();
+                    hashed.skip_player();
+                    continue;
+                }
+
+                let pos = moves[rng.gen_range(0..moves.len())];
+                assert_eq!(packed.place_traced(&pos), hashed.place_traced(&pos));
+                assert_eq!(packed.grid, hashed.grid);
+                assert_eq!(
+                    cells(&packed.empty_cells.borrow()),
+                    cells(&hashed.empty_cells.borrow())
+                );
+                for player in 0..players {
+                    let owned = |game: &ChainReaction| cells(&game.owned_cells.borrow()[player]);
+                    assert_eq!(owned(&packed), owned(&hashed));
+                    assert_eq!(packed.player_mass(player), hashed.player_mass(player));
+                }
+                assert!(packed.legal_moves().eq(hashed.legal_moves()));
+                assert_eq!(packed.legal_move_count(), hashed.legal_move_count());
+                for cell in 0..width * height {
+                    let pos = Pos(cell % width, cell / width);
+                    for radius in 1..4 {
+                        assert_eq!(
+                            packed.mass_within(&pos, radius),
+                            hashed.mass_within(&pos, radius)
+                        );
+                    }
+                }
+                assert_eq!(*packed.eliminations(), *hashed.eliminations());
+                assert_eq!(packed.game_outcome(), hashed.game_outcome());
+            }
+            assert!(!hashed.active());
+        }
+    }
+
+    #[test]
+    fn storage_backends_agree() {
+        compare_storage(Rules::default(), None, false, 1);
+        compare_storage(Rules::default(), None, true, 2);
+
+        let variants = Rules {
+            capacity_offset: 1,
+            keep_excess: true,
+            resolution: Resolution::DepthFirst,
+            place_on_opponents: true,
+        };
+        compare_storage(variants, None, false, 3);
+        compare_storage(
+            Rules {
+                capacity_offset: 0,
+                ..variants
+            },
+            None,
+            true,
+            4,
+        );
+
+        for &convert_allies in &[false, true] {
+            let teams = Teams {
+                assignment: vec![0, 1, 0, 1],
+                convert_allies,
+            };
+            compare_storage(Rules::default(), Some(teams.clone()), false, 5);
+            compare_storage(variants, Some(teams), false, 6);
+        }
+
+        let game = ChainReaction::new(17, 4, 2).unwrap();
+        assert!(game.planes.borrow().is_none());
+        game.place(&Pos(16, 3)).unwrap();
+        assert_eq!(
+            game.owned_cells.borrow()[0].iter().collect::<Vec<_>>(),
+            [Pos(16, 3)]
+        );
+        assert_eq!(game.legal_move_count(), 67);
+    }
+}
@@ -0,0 +1,186 @@
+use chain_reaction::{Limits, Resolution, Rules, Teams};
+use checkpoint::Checkpoint;
+use clap::{Parser, Subcommand};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::fs;
+
+mod analyze;
+#[cfg(feature = "animation")]
+mod animate;
+mod board;
+mod checkpoint;
+mod conformance;
+mod heatmap;
+mod interactive;
+mod progress;
+mod render;
+mod replay;
+mod simulate;
+mod table;
+mod tournament;
+
+#[derive(Parser)]
+#[command(
+    name = "chain_reaction",
+    about = "Simulate, play and analyze Chain Reaction games."
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Play many games between bots and tally the winners
+    Simulate(simulate::Args),
+    /// Play against the bots in the terminal
+    Play(interactive::Args),
+    /// Step through a recorded game
+    Replay(replay::Args),
+    /// Draw a position
+    Render(render::Args),
+    /// Turn a game record into an animated GIF or APNG
+    #[cfg(feature = "animation")]
+    Animate(animate::Args),
+    /// Estimate how good each move is in a position using playouts
+    Analyze(analyze::Args),
+    /// Play a round-robin between strategies
+    Tournament(tournament::Args),
+    /// Show where strategies play, explode and capture over many games
+    Heatmap(heatmap::Args),
+    /// Regenerate or check the corpus of games for other rule engines
+    Conformance(conformance::Args),
+}
+
+#[derive(clap::Args)]
+pub struct BoardArgs {
+    /// Board width
+    #[arg(long, default_value_t = 5)]
+    pub width: usize,
+
+    /// Board height
+    #[arg(long, default_value_t = 5)]
+    pub height: usize,
+}
+
+#[derive(clap::Args)]
+pub struct LimitArgs {
+    /// Declare a draw when a single explosion takes more waves than this
+    #[arg(long)]
+    pub max_waves: Option<u32>,
+
+    /// Abort games that last longer than this many moves
+    #[arg(long)]
+    pub max_plies: Option<u32>,
+}
+
+impl LimitArgs {
+    pub fn limits(&self) -> Limits {
+        Limits {
+            max_waves: self.max_waves,
+            max_plies: self.max_plies,
+        }
+    }
+}
+
+#[derive(clap::Args)]
+pub struct RuleArgs {
+    /// Added to the neighbour count to get the capacity of a cell
+    #[arg(long, default_value_t = 0)]
+    pub capacity_offset: u32,
+
+    /// Let exploding cells keep the orbs they do not send out
+    #[arg(long)]
+    pub keep_excess: bool,
+
+    /// Follow each explosion through before handing out the next orb
+    #[arg(long)]
+    pub depth_first: bool,
+
+    /// Allow placing on opponents' cells, taking them over
+    #[arg(long)]
+    pub place_on_opponents: bool,
+}
+
+impl RuleArgs {
+    pub fn rules(&self) -> Rules {
+        Rules {
+            capacity_offset: self.capacity_offset,
+            keep_excess: self.keep_excess,
+            resolution: if self.depth_first {
+                Resolution::DepthFirst
+            } else {
+                Resolution::BreadthFirst
+            },
+            place_on_opponents: self.place_on_opponents,
+        }
+    }
+}
+
+#[derive(clap::Args)]
+pub struct TeamArgs {
+    /// Team of every player in turn order, e.g. `0,1,0,1`
+    #[arg(long, value_delimiter = ',')]
+    pub teams: Option<Vec<usize>>,
+
+    /// Let explosions take over allied cells instead of adding to them
+    #[arg(long, requires = "teams")]
+    pub convert_allies: bool,
+}
+
+impl TeamArgs {
+    pub fn teams(&self, players: usize) -> Teams {
+        match &self.teams {
+            Some(assignment) => Teams {
+                assignment: assignment.clone(),
+                convert_allies: self.convert_allies,
+            },
+            None => Teams::solo(players),
+        }
+    }
+}
+
+#[derive(clap::Args)]
+pub struct SeedArgs {
+    /// Seed for the random number generator. A random seed is used if omitted.
+    #[arg(long)]
+    pub seed: Option<u64>,
+}
+
+impl SeedArgs {
+    pub fn seed(&self) -> u64 {
+        self.seed
+            .unwrap_or_else(|| ChaCha8Rng::from_entropy().next_u64())
+    }
+
+    /// The seed given, or when resuming without one, the seed the run
+    /// started with.
+    pub fn resumed_seed(&self, resumed: Option<&Checkpoint>) -> Result<u64, String> {
+        match (resumed, self.seed) {
+            (Some(checkpoint), None) => checkpoint.settings["seed"]
+                .as_u64()
+                .ok_or_else(|| "The checkpoint does not record a seed.".to_string()),
+            _ => Ok(self.seed()),
+        }
+    }
+}
+
+pub fn read_file(path: &str) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|err| format!("Could not read {}: {}", path, err))
+}
+
+pub fn run() -> Result<(), String> {
+    match Cli::parse().command {
+        Command::Simulate(args) => simulate::run(args),
+        Command::Play(args) => interactive::run(args),
+        Command::Replay(args) => replay::run(args),
+        Command::Render(args) => render::run(args),
+        #[cfg(feature = "animation")]
+        Command::Animate(args) => animate::run(args),
+        Command::Analyze(args) => analyze::run(args),
+        Command::Tournament(args) => tournament::run(args),
+        Command::Heatmap(args) => heatmap::run(args),
+        Command::Conformance(args) => conformance::run(args),
+    }
+}
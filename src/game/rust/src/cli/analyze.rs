@@ -0,0 +1,82 @@
+use super::{
+    read_file,
+    table::{ratio, FormatArgs, Table},
+    SeedArgs,
+};
+use chain_reaction::{from_spec, parse_position, GameContext, Outcome, Player, Pos, Runner};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde_json::Value;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Position to analyze
+    file: String,
+
+    /// Number of games played out after each candidate move
+    #[arg(long, default_value_t = 100)]
+    playouts: u32,
+
+    /// Strategy used by every player during playouts
+    #[arg(long, default_value = "play_randomly")]
+    strategy: String,
+
+    #[command(flatten)]
+    seed: SeedArgs,
+
+    #[command(flatten)]
+    format: FormatArgs,
+}
+
+pub fn run(args: Args) -> Result<(), String> {
+    let position = parse_position(&read_file(&args.file)?)?;
+    let player = position.current_player();
+    let seed = args.seed.seed();
+
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let candidates = GameContext::new(&position, &mut rng).available_cells();
+
+    let players = (0..position.players())
+        .map(|_| from_spec(&args.strategy))
+        .collect::<Result<Vec<Box<dyn Player>>, _>>()?;
+    let mut runner = Runner::new(position.width(), position.height(), players)?;
+    runner.seed(seed);
+    runner.set_rules(position.rules())?;
+    runner.set_capacity(position.custom_capacity().cloned())?;
+
+    let mut results = Vec::new();
+    for pos in candidates {
+        let mut wins = 0u32;
+        for _ in 0..args.playouts {
+            let game = position.clone();
+            game.place(&pos)?;
+            if runner.play_from(game)? == Outcome::Win(player) {
+                wins += 1;
+            }
+        }
+        results.push((pos, wins));
+    }
+
+    results.sort_by_key(|&(pos, wins)| (std::cmp::Reverse(wins), pos));
+
+    let mut table = Table::new(["x", "y", "wins", "playouts", "win_rate"]);
+    for (Pos(x, y), wins) in results {
+        table.push(vec![
+            Value::from(x),
+            Value::from(y),
+            Value::from(wins),
+            Value::from(args.playouts),
+            ratio(u64::from(wins), u64::from(args.playouts)),
+        ]);
+    }
+
+    if args.format.is_human() {
+        println!(
+            "Moves for player {} ranked by win rate over {} playouts (seed {})\n",
+            player, args.playouts, seed
+        );
+    }
+    table.print(&args.format);
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use super::read_file;
+use chain_reaction::{render_animation, AnimationFormat, AnimationStyle, GameRecord};
+use std::fs;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Game record to animate
+    file: String,
+
+    /// Image to write. Files ending in `.png` or `.apng` are written as APNG,
+    /// anything else as GIF.
+    #[arg(long, short)]
+    output: String,
+
+    /// How long each move is shown, in milliseconds
+    #[arg(long, default_value_t = 500)]
+    delay: u16,
+
+    /// Add a frame for every wave of an explosion
+    #[arg(long)]
+    waves: bool,
+
+    /// How long each explosion wave is shown, in milliseconds
+    #[arg(long, default_value_t = 150)]
+    wave_delay: u16,
+
+    /// Side of a cell in pixels
+    #[arg(long, default_value_t = 32)]
+    cell_size: u32,
+}
+
+pub fn run(args: Args) -> Result<(), String> {
+    let record = GameRecord::parse(&read_file(&args.file)?)?;
+
+    let format = if args.output.ends_with(".png") || args.output.ends_with(".apng") {
+        AnimationFormat::Apng
+    } else {
+        AnimationFormat::Gif
+    };
+    let style = AnimationStyle {
+        cell_size: args.cell_size,
+        frame_delay: args.delay,
+        wave_frames: args.waves,
+        wave_delay: args.wave_delay,
+    };
+
+    let image = render_animation(&record, &style, format)?;
+    fs::write(&args.output, image)
+        .map_err(|err| format!("Could not write {}: {}", args.output, err))
+}
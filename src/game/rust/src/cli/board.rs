@@ -0,0 +1,37 @@
+use chain_reaction::{ansi_color, TextStyle};
+use std::env;
+
+#[derive(clap::Args)]
+pub struct StyleArgs {
+    /// Draw the board with plain ASCII characters
+    #[arg(long)]
+    pub ascii: bool,
+
+    /// Disable colours. Also respected through the NO_COLOR environment variable.
+    #[arg(long)]
+    pub no_color: bool,
+}
+
+impl StyleArgs {
+    pub fn style(&self, coordinates: bool) -> TextStyle {
+        TextStyle {
+            color: !self.no_color && env::var_os("NO_COLOR").is_none(),
+            unicode: !self.ascii,
+            coordinates,
+            highlight_critical: true,
+        }
+    }
+}
+
+pub fn player_label(player: usize, name: &str, style: &TextStyle) -> String {
+    if style.color {
+        format!(
+            "\x1b[{}mPlayer {}\x1b[0m ({})",
+            ansi_color(player),
+            player,
+            name
+        )
+    } else {
+        format!("Player {} ({})", player, name)
+    }
+}
@@ -0,0 +1,228 @@
+use chain_reaction::{RunReport, RunnerState};
+use serde_json::{json, Value};
+use std::{
+    convert::TryFrom,
+    fs,
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+#[derive(clap::Args)]
+pub struct CheckpointArgs {
+    /// Save how far the games got to this file, so that the run can be resumed
+    #[arg(long = "checkpoint", id = "checkpoint", value_name = "FILE")]
+    pub file: Option<String>,
+
+    /// Seconds between saves of the checkpoint
+    #[arg(long, default_value_t = 60, requires = "checkpoint")]
+    checkpoint_every: u64,
+
+    /// Continue from the checkpoint instead of starting over
+    #[arg(long, requires = "checkpoint")]
+    resume: bool,
+}
+
+impl CheckpointArgs {
+    /// The checkpoint to continue from, if asked to resume.
+    pub fn resumed(&self) -> Result<Option<Checkpoint>, String> {
+        match &self.file {
+            Some(path) if self.resume => Checkpoint::load(path).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// The checkpoint to keep for a run with `settings`: the resumed one,
+    /// which must have been made with the same settings, or a new one if a
+    /// file is given.
+    pub fn open(
+        &self,
+        resumed: Option<Checkpoint>,
+        settings: Value,
+    ) -> Result<Option<Checkpoint>, String> {
+        match (resumed, &self.file) {
+            (Some(checkpoint), _) if checkpoint.settings != settings => {
+                Err("The checkpoint was made with different settings.".to_string())
+            }
+            (Some(checkpoint), _) => Ok(Some(checkpoint)),
+            (None, Some(path)) => Ok(Some(Checkpoint::new(path, settings))),
+            (None, None) => Ok(None),
+        }
+    }
+
+    pub fn every(&self) -> Duration {
+        Duration::from_secs(self.checkpoint_every)
+    }
+}
+
+/// How far every runner of a simulation got, saved so that an interrupted
+/// simulation can pick up where it stopped.
+pub struct Checkpoint {
+    path: String,
+    /// What is being simulated. A checkpoint only resumes a simulation
+    /// with the same settings.
+    pub settings: Value,
+    /// Time spent playing in earlier sessions.
+    earlier: Duration,
+    started: Instant,
+    runners: Mutex<Vec<Option<RunnerCheckpoint>>>,
+}
+
+/// A runner as it was after the last game it finished.
+#[derive(Clone)]
+pub struct RunnerCheckpoint {
+    pub games: u32,
+    pub report: RunReport,
+    pub state: RunnerState,
+}
+
+impl Checkpoint {
+    pub fn new(path: &str, settings: Value) -> Checkpoint {
+        Checkpoint {
+            path: path.to_string(),
+            settings,
+            earlier: Duration::ZERO,
+            started: Instant::now(),
+            runners: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn load(path: &str) -> Result<Checkpoint, String> {
+        let contents = super::read_file(path)?;
+        let malformed = || format!("{} is not a valid checkpoint.", path);
+        let value: Value = serde_json::from_str(&contents).map_err(|_| malformed())?;
+
+        let runners = value["runners"]
+            .as_array()
+            .ok_or_else(malformed)?
+            .iter()
+            .map(|runner| match runner {
+                Value::Null => Some(None),
+                runner => parse_runner(runner).map(Some),
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(malformed)?;
+
+        Ok(Checkpoint {
+            path: path.to_string(),
+            settings: value["settings"].clone(),
+            earlier: Duration::from_millis(value["elapsed_ms"].as_u64().ok_or_else(malformed)?),
+            started: Instant::now(),
+            runners: Mutex::new(runners),
+        })
+    }
+
+    /// Time spent playing, including earlier sessions.
+    pub fn elapsed(&self) -> Duration {
+        self.earlier + self.started.elapsed()
+    }
+
+    /// Games finished by every runner together.
+    pub fn games(&self) -> u32 {
+        self.lock()
+            .iter()
+            .flatten()
+            .map(|runner| runner.games)
+            .sum()
+    }
+
+    pub fn runner(&self, index: usize) -> Option<RunnerCheckpoint> {
+        self.lock().get(index).cloned().flatten()
+    }
+
+    pub fn update(&self, index: usize, runner: RunnerCheckpoint) {
+        let mut runners = self.lock();
+        if runners.len() <= index {
+            runners.resize(index + 1, None);
+        }
+        runners[index] = Some(runner);
+    }
+
+    /// Writes the checkpoint next to its file first and then moves it into
+    /// place, so that an interruption never leaves a half-written file.
+    pub fn save(&self) -> Result<(), String> {
+        let runners: Vec<_> = self
+            .lock()
+            .iter()
+            .map(|runner| runner.as_ref().map_or(Value::Null, format_runner))
+            .collect();
+        let value = json!({
+            "settings": self.settings,
+            "elapsed_ms": self.elapsed().as_millis() as u64,
+            "runners": runners,
+        });
+
+        let temporary = format!("{}.tmp", self.path);
+        fs::write(&temporary, value.to_string())
+            .and_then(|_| fs::rename(&temporary, &self.path))
+            .map_err(|err| format!("Could not write {}: {}", self.path, err))
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<Option<RunnerCheckpoint>>> {
+        self.runners.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+fn format_runner(runner: &RunnerCheckpoint) -> Value {
+    let RunnerCheckpoint {
+        games,
+        report,
+        state,
+    } = runner;
+    let seed: String = state
+        .seed
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    json!({
+        "games": games,
+        "wins": report.wins,
+        "team_wins": report.team_wins,
+        "draws": report.draws,
+        "aborted": report.aborted,
+        "seed": seed,
+        "stream": state.stream,
+        // Too large for a JSON number.
+        "word_pos": state.word_pos.to_string(),
+        "players": state.players,
+    })
+}
+
+fn parse_runner(value: &Value) -> Option<RunnerCheckpoint> {
+    let counts = |key: &str| -> Option<Vec<usize>> {
+        value[key]
+            .as_array()?
+            .iter()
+            .map(|count| count.as_u64().map(|count| count as usize))
+            .collect()
+    };
+
+    let hex = value["seed"].as_str()?;
+    let mut seed = [0; 32];
+    if hex.len() != 2 * seed.len() || !hex.is_ascii() {
+        return None;
+    }
+    for (i, byte) in seed.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+    }
+
+    Some(RunnerCheckpoint {
+        games: u32::try_from(value["games"].as_u64()?).ok()?,
+        report: RunReport {
+            wins: counts("wins")?,
+            team_wins: counts("team_wins")?,
+            draws: value["draws"].as_u64()? as usize,
+            aborted: value["aborted"].as_u64()? as usize,
+        },
+        state: RunnerState {
+            seed,
+            stream: value["stream"].as_u64()?,
+            word_pos: value["word_pos"].as_str()?.parse().ok()?,
+            players: value["players"]
+                .as_array()?
+                .iter()
+                .map(|state| state.as_str().map(String::from))
+                .collect::<Option<_>>()?,
+        },
+    })
+}
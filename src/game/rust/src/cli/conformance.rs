@@ -0,0 +1,97 @@
+use super::read_file;
+use chain_reaction::{generate_corpus, ConformanceCase};
+use clap::Subcommand;
+use std::{fs, path::Path};
+
+/// Seed the stored corpus was generated with.
+const CORPUS_SEED: u64 = 1;
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(subcommand)]
+    action: Action,
+
+    /// Directory holding one file per case
+    #[arg(long, default_value = "conformance", global = true)]
+    dir: String,
+}
+
+#[derive(Subcommand)]
+enum Action {
+    /// Replace the corpus with freshly played games
+    Generate,
+    /// Check that every case still replays to its position and result
+    Verify,
+}
+
+pub fn run(args: Args) -> Result<(), String> {
+    let dir = Path::new(&args.dir);
+    match args.action {
+        Action::Generate => generate(dir),
+        Action::Verify => verify(dir),
+    }
+}
+
+fn generate(dir: &Path) -> Result<(), String> {
+    let cases = generate_corpus(CORPUS_SEED)?;
+    fs::create_dir_all(dir)
+        .map_err(|err| format!("Could not create {}: {}", dir.display(), err))?;
+
+    // Cases that are no longer generated would otherwise linger.
+    for path in case_files(dir)? {
+        fs::remove_file(&path)
+            .map_err(|err| format!("Could not remove {}: {}", path.display(), err))?;
+    }
+
+    for (name, case) in &cases {
+        let path = dir.join(format!("{}.txt", name));
+        let contents = format!(
+            "# Generated by `chain_reaction conformance generate`.\n{}\n",
+            case
+        );
+        fs::write(&path, contents)
+            .map_err(|err| format!("Could not write {}: {}", path.display(), err))?;
+    }
+
+    println!("Wrote {} cases to {}.", cases.len(), dir.display());
+    Ok(())
+}
+
+fn verify(dir: &Path) -> Result<(), String> {
+    let files = case_files(dir)?;
+    if files.is_empty() {
+        return Err(format!("No cases found in {}.", dir.display()));
+    }
+
+    let mut failures = 0;
+    for path in &files {
+        let result = ConformanceCase::parse(&read_file(&path.to_string_lossy())?)
+            .and_then(|case| case.verify());
+        if let Err(err) = result {
+            println!("{}: {}", path.display(), err);
+            failures += 1;
+        }
+    }
+
+    if failures > 0 {
+        return Err(format!("{} of {} cases failed.", failures, files.len()));
+    }
+    println!("All {} cases passed.", files.len());
+    Ok(())
+}
+
+/// Case files in `dir`, in name order.
+fn case_files(dir: &Path) -> Result<Vec<std::path::PathBuf>, String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(format!("Could not read {}: {}", dir.display(), err)),
+    };
+
+    let mut files: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
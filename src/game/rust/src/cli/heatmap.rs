@@ -0,0 +1,104 @@
+use super::{board::StyleArgs, simulate::build_players, BoardArgs, LimitArgs, RuleArgs, SeedArgs};
+use chain_reaction::{
+    heatmap_csv, render_heatmap_svg, render_heatmap_text, Heatmap, PlayerSpec, Runner,
+};
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum Metric {
+    /// Share of a strategy's moves that went to each cell
+    Choices,
+    /// Explosions per game
+    Explosions,
+    /// Captures per game
+    Captures,
+    /// Win rate of the first player after opening on each cell
+    Openings,
+}
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    board: BoardArgs,
+
+    /// Names of the strategies, one per player
+    #[arg(required = true, num_args = 2..)]
+    players: Vec<String>,
+
+    /// Number of games to play
+    #[arg(long, default_value_t = 1000)]
+    games: u32,
+
+    /// Statistic to show
+    #[arg(long, value_enum, default_value_t = Metric::Choices)]
+    metric: Metric,
+
+    /// Strategy whose moves are shown by the `choices` metric, over every
+    /// player using it. Defaults to the first player's
+    #[arg(long)]
+    strategy: Option<String>,
+
+    /// Draw an SVG image instead of text
+    #[arg(long, conflicts_with = "csv")]
+    svg: bool,
+
+    /// Print the values as CSV
+    #[arg(long)]
+    csv: bool,
+
+    /// Side of a cell in pixels for SVG output
+    #[arg(long, default_value_t = 48)]
+    cell_size: u32,
+
+    #[command(flatten)]
+    seed: SeedArgs,
+
+    #[command(flatten)]
+    rules: RuleArgs,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    style: StyleArgs,
+}
+
+pub fn run(args: Args) -> Result<(), String> {
+    let (width, height) = (args.board.width, args.board.height);
+    let players = build_players(&args.players)?;
+    // Written out the same way, so that `--strategy` matches however the
+    // spec was spaced.
+    let normalize = |spec: &str| PlayerSpec::parse(spec).map(|spec| spec.to_string());
+    let strategies = args
+        .players
+        .iter()
+        .map(|spec| normalize(spec))
+        .collect::<Result<Vec<_>, _>>()?;
+    let strategy = match &args.strategy {
+        Some(spec) => normalize(spec)?,
+        None => strategies[0].clone(),
+    };
+
+    let mut heatmap = Heatmap::new(width, height, strategies)?;
+    let mut runner = Runner::new(width, height, players)?;
+    runner.seed(args.seed.seed());
+    runner.set_rules(args.rules.rules())?;
+    runner.set_limits(args.limits.limits());
+    runner.run_observed(args.games, &mut heatmap)?;
+
+    let map = match args.metric {
+        Metric::Choices => heatmap.choices(&strategy)?,
+        Metric::Explosions => heatmap.explosions()?,
+        Metric::Captures => heatmap.captures()?,
+        Metric::Openings => heatmap.opening_win_rate()?,
+    };
+
+    if args.svg {
+        print!("{}", render_heatmap_svg(&map, args.cell_size));
+    } else if args.csv {
+        print!("{}", heatmap_csv(&map));
+    } else {
+        print!("{}", render_heatmap_text(&map, &args.style.style(true)));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,229 @@
+use super::{
+    board::{player_label, StyleArgs},
+    BoardArgs, RuleArgs, SeedArgs, TeamArgs,
+};
+use chain_reaction::{
+    from_spec, render_text, AvoidOthers, ChainReaction, GameContext, GameRecord, Outcome, Player,
+    Pos, Rules, Teams, TextStyle,
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::fs;
+use std::io::{self, BufRead, Write};
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    board: BoardArgs,
+
+    /// Seats in turn order: `human` or a strategy spec such as `form_chains`
+    #[arg(required = true, num_args = 2..)]
+    players: Vec<String>,
+
+    #[command(flatten)]
+    seed: SeedArgs,
+
+    #[command(flatten)]
+    rules: RuleArgs,
+
+    #[command(flatten)]
+    teams: TeamArgs,
+
+    #[command(flatten)]
+    style: StyleArgs,
+
+    /// Write the game record to this file when the game ends
+    #[arg(long)]
+    save: Option<String>,
+}
+
+pub enum Seat {
+    Human,
+    Bot(String, Box<dyn Player>),
+}
+
+impl Seat {
+    pub fn parse(name: &str) -> Result<Seat, &'static str> {
+        match name {
+            "human" => Ok(Seat::Human),
+            _ => Ok(Seat::Bot(name.to_string(), from_spec(name)?)),
+        }
+    }
+
+    fn is_human(&self) -> bool {
+        matches!(self, Seat::Human)
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Seat::Human => "human",
+            Seat::Bot(name, _) => name,
+        }
+    }
+}
+
+enum Command {
+    Place(Pos),
+    Undo,
+    Hint,
+    Help,
+    Quit,
+}
+
+fn parse_command(line: &str) -> Result<Command, &'static str> {
+    let line = line.trim();
+
+    match line {
+        "undo" | "u" => return Ok(Command::Undo),
+        "hint" | "h" => return Ok(Command::Hint),
+        "help" | "?" => return Ok(Command::Help),
+        "quit" | "q" => return Ok(Command::Quit),
+        _ => {}
+    }
+
+    let coords: Vec<_> = line
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    match coords.as_slice() {
+        [x, y] => {
+            let x = x.parse().map_err(|_| "Invalid x coordinate.")?;
+            let y = y.parse().map_err(|_| "Invalid y coordinate.")?;
+            Ok(Command::Place(Pos(x, y)))
+        }
+        _ => Err("Unrecognized command. Type 'help' for a list of commands."),
+    }
+}
+
+/// Drops moves from the end of the game until a move made by a human has
+/// been taken back, then rebuilds the game from what remains.
+fn undo(game: &ChainReaction, seats: &[Seat]) -> Option<ChainReaction> {
+    let mut record = GameRecord::from_game(game);
+
+    let last_human = record
+        .moves
+        .iter()
+        .rposition(|m| seats[m.player].is_human())?;
+    record.moves.truncate(last_human);
+    record.replay().ok()
+}
+
+fn print_help() {
+    println!("Commands:");
+    println!("  x y    place a cell at column x, row y (also 'x,y')");
+    println!("  undo   take back your last move");
+    println!("  hint   suggest a move");
+    println!("  quit   leave the game");
+}
+
+pub fn run(args: Args) -> Result<(), String> {
+    let seats = args
+        .players
+        .iter()
+        .map(|name| Seat::parse(name))
+        .collect::<Result<Vec<_>, _>>()?;
+    let rng = ChaCha8Rng::seed_from_u64(args.seed.seed());
+
+    let style = args.style.style(true);
+
+    let teams = args.teams.teams(seats.len());
+    let game = play(
+        args.board.width,
+        args.board.height,
+        seats,
+        rng,
+        args.rules.rules(),
+        teams,
+        &style,
+    )?;
+    if let Some(path) = args.save {
+        fs::write(&path, GameRecord::from_game(&game).to_string())
+            .map_err(|err| format!("Could not write {}: {}", path, err))?;
+    }
+    Ok(())
+}
+
+fn play(
+    width: usize,
+    height: usize,
+    mut seats: Vec<Seat>,
+    mut rng: ChaCha8Rng,
+    rules: Rules,
+    teams: Teams,
+    style: &TextStyle,
+) -> Result<ChainReaction, &'static str> {
+    let mut game = ChainReaction::with_rules(width, height, seats.len(), rules)?;
+    game.set_teams(teams)?;
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+
+    print_help();
+
+    while game.active() {
+        let player = game.current_player();
+        if !game.player_is_alive(player) || game.legal_move_count() == 0 {
+            game.skip_player();
+            continue;
+        }
+        let context = GameContext::new(&game, &mut rng);
+
+        if let Seat::Bot(_, bot) = &mut seats[player] {
+            let pos = bot.play(context)?;
+            game.place(&pos)?;
+            println!(
+                "{} plays {} {}",
+                player_label(player, seats[player].name(), style),
+                pos.0,
+                pos.1
+            );
+            continue;
+        }
+
+        println!();
+        print!("{}", render_text(&game, style));
+        print!("{} > ", player_label(player, seats[player].name(), style));
+        io::stdout()
+            .flush()
+            .map_err(|_| "Failed to write output.")?;
+
+        let line = match lines.next() {
+            Some(line) => line.map_err(|_| "Failed to read input.")?,
+            None => return Ok(game),
+        };
+
+        match parse_command(&line) {
+            Ok(Command::Place(pos)) => {
+                if let Err(err) = game.place(&pos) {
+                    println!("Illegal move: {}", err);
+                }
+            }
+            Ok(Command::Undo) => match undo(&game, &seats) {
+                Some(previous) => game = previous,
+                None => println!("Nothing to undo."),
+            },
+            Ok(Command::Hint) => {
+                let Pos(x, y) = AvoidOthers::new().play(context)?;
+                println!("Hint: try {} {}", x, y);
+            }
+            Ok(Command::Help) => print_help(),
+            Ok(Command::Quit) => return Ok(game),
+            Err(err) => println!("{}", err),
+        }
+    }
+
+    println!();
+    print!("{}", render_text(&game, style));
+    match game.outcome() {
+        Some(Outcome::Win(winner)) if !game.teams().is_solo() => {
+            println!("Team {} wins!", game.team(winner))
+        }
+        Some(Outcome::Win(winner)) => println!(
+            "{} wins!",
+            player_label(winner, seats[winner].name(), style)
+        ),
+        _ => println!("The game ended without a winner."),
+    }
+
+    Ok(game)
+}
@@ -0,0 +1,117 @@
+use chain_reaction::{ChainReaction, RunEvent, RunObserver};
+use std::{
+    io::{self, IsTerminal, Write},
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+    time::{Duration, Instant},
+};
+
+/// Width of the bar itself, in characters.
+const BAR_WIDTH: usize = 30;
+
+/// Games finished so far across every simulation thread, and whether the
+/// user asked to stop.
+pub struct Progress {
+    total: u32,
+    /// Games that were already finished when the progress started, such as
+    /// those of a resumed simulation.
+    initial: u32,
+    finished: AtomicU32,
+    cancelled: AtomicBool,
+    started: Instant,
+}
+
+impl Progress {
+    pub fn new(total: u32, finished: u32) -> Progress {
+        Progress {
+            total,
+            initial: finished,
+            finished: AtomicU32::new(finished),
+            cancelled: AtomicBool::new(false),
+            started: Instant::now(),
+        }
+    }
+
+    /// Asks every runner to stop before its next move.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Redraws the bar on stderr, if it is a terminal.
+    pub fn draw(&self) {
+        if io::stderr().is_terminal() {
+            eprint!("\r{}\x1b[K", self.line());
+        }
+    }
+
+    /// Removes the bar, leaving the cursor at the start of the line.
+    pub fn clear(&self) {
+        if io::stderr().is_terminal() {
+            eprint!("\r\x1b[K");
+            let _ = io::stderr().flush();
+        }
+    }
+
+    fn line(&self) -> String {
+        let finished = self.finished.load(Ordering::Relaxed);
+        let fraction = if self.total == 0 {
+            1.0
+        } else {
+            f64::from(finished) / f64::from(self.total)
+        };
+        let filled = (fraction * BAR_WIDTH as f64) as usize;
+
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let rate = if elapsed > 0.0 {
+            f64::from(finished - self.initial) / elapsed
+        } else {
+            0.0
+        };
+        let eta = if rate > 0.0 {
+            let left = f64::from(self.total - finished) / rate;
+            format_duration(Duration::from_secs_f64(left))
+        } else {
+            "?".to_string()
+        };
+
+        format!(
+            "[{}{}] {}/{} games, {:.0} games/s, ETA {}",
+            "#".repeat(filled),
+            ".".repeat(BAR_WIDTH - filled),
+            finished,
+            self.total,
+            rate,
+            eta
+        )
+    }
+}
+
+/// Counts the games a runner finishes and stops it once the run is
+/// cancelled.
+pub struct ProgressObserver<'a>(pub &'a Progress);
+
+impl RunObserver for ProgressObserver<'_> {
+    fn observe(&mut self, event: &RunEvent, _game: &ChainReaction) {
+        if let RunEvent::GameFinished { .. } = event {
+            self.0.finished.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn should_stop(&mut self) -> bool {
+        self.0.is_cancelled()
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, secs % 3600 / 60)
+    } else if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
@@ -0,0 +1,84 @@
+use super::{board::StyleArgs, read_file};
+use chain_reaction::{
+    parse_pos, parse_position, render_svg, render_text, ChainReaction, Pos, SvgStyle,
+};
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Position to draw
+    file: String,
+
+    /// Label rows and columns
+    #[arg(long)]
+    coordinates: bool,
+
+    /// Draw an SVG image instead of text
+    #[arg(long)]
+    svg: bool,
+
+    /// Play this move (`x,y`) before drawing and highlight it
+    #[arg(long, value_parser = parse_pos)]
+    play: Option<Pos>,
+
+    /// Draw arrows for this explosion wave of the played move, counting from 0
+    #[arg(long, requires = "play")]
+    wave: Option<usize>,
+
+    /// Side of a cell in pixels for SVG output
+    #[arg(long, default_value_t = 48)]
+    cell_size: u32,
+
+    #[command(flatten)]
+    style: StyleArgs,
+}
+
+pub fn run(args: Args) -> Result<(), String> {
+    let game = parse_position(&read_file(&args.file)?)?;
+    // Arrows show where the orbs of a wave go, so they are drawn on the
+    // board as it was before the wave: before the move for the first one,
+    // and after the one before it otherwise.
+    let mut before_wave = (args.wave == Some(0)).then(|| game.clone());
+
+    let explosion = match args.play {
+        Some(pos) => {
+            let mut waves = 0;
+            let mut on_wave = |game: &ChainReaction, _: &[Pos]| {
+                waves += 1;
+                if args.wave == Some(waves) {
+                    before_wave = Some(game.clone());
+                }
+            };
+            Some(game.place_stepwise(&pos, &mut on_wave)?)
+        }
+        None => None,
+    };
+
+    if args.svg {
+        let wave = match (&explosion, args.wave) {
+            (Some(explosion), Some(index)) => {
+                explosion.waves.get(index).cloned().ok_or_else(|| {
+                    format!("The move only caused {} waves.", explosion.waves.len())
+                })?
+            }
+            _ => Vec::new(),
+        };
+
+        let style = SvgStyle {
+            cell_size: args.cell_size,
+            coordinates: args.coordinates,
+            last_move: args.play,
+            wave,
+        };
+        print!(
+            "{}",
+            render_svg(before_wave.as_ref().unwrap_or(&game), &style)
+        );
+    } else {
+        print!(
+            "{}",
+            render_text(&game, &args.style.style(args.coordinates))
+        );
+    }
+
+    Ok(())
+}
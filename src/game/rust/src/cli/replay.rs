@@ -0,0 +1,82 @@
+use super::{
+    board::StyleArgs,
+    read_file,
+    table::{FormatArgs, Table},
+};
+use chain_reaction::{render_text, GameRecord, Outcome, Pos};
+use serde_json::Value;
+
+#[derive(clap::Args)]
+pub struct Args {
+    /// Game record to replay
+    file: String,
+
+    #[command(flatten)]
+    format: FormatArgs,
+
+    #[command(flatten)]
+    style: StyleArgs,
+}
+
+pub fn run(args: Args) -> Result<(), String> {
+    let record = GameRecord::parse(&read_file(&args.file)?)?;
+    let game = record.new_game()?;
+    let style = args.style.style(false);
+
+    let columns = ["ply", "player", "x", "y"]
+        .iter()
+        .map(|c| c.to_string())
+        .chain((0..record.players).map(|p| format!("mass_{}", p)));
+    let mut table = Table::new(columns);
+
+    for (ply, m) in record.moves.iter().enumerate() {
+        game.skip_to(m.player)
+            .map_err(|_| format!("Move {} is made by an unknown player.", ply + 1))?;
+        game.place(&m.pos)
+            .map_err(|err| format!("Move {} is illegal: {}", ply + 1, err))?;
+
+        let Pos(x, y) = m.pos;
+        if args.format.is_human() {
+            println!("Move {}: player {} plays {} {}", ply + 1, m.player, x, y);
+            println!("{}", render_text(&game, &style));
+        }
+
+        let mut row = vec![
+            Value::from(ply + 1),
+            Value::from(m.player),
+            Value::from(x),
+            Value::from(y),
+        ];
+        row.extend((0..record.players).map(|p| Value::from(game.player_mass(p))));
+        table.push(row);
+    }
+
+    if args.format.is_human() {
+        for elimination in game.eliminations().iter() {
+            match elimination.by {
+                Some(by) => println!(
+                    "Player {} was eliminated by player {} on move {}.",
+                    elimination.player, by, elimination.ply
+                ),
+                None => println!("Player {} was already out.", elimination.player),
+            }
+        }
+
+        match game.game_outcome() {
+            Some(outcome) => {
+                match outcome.result {
+                    Outcome::Win(winner) => println!("Player {} wins.", winner),
+                    Outcome::Draw => println!("The game is a draw."),
+                    Outcome::Aborted => println!("The game was aborted."),
+                }
+                let ranking: Vec<_> = outcome.ranking.iter().map(|p| p.to_string()).collect();
+                println!("Final standings: {}", ranking.join(", "));
+            }
+            None => println!("The game is unfinished."),
+        }
+    } else {
+        table.print(&args.format);
+    }
+
+    Ok(())
+}
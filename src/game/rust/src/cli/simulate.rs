@@ -0,0 +1,305 @@
+use super::{
+    checkpoint::{Checkpoint, CheckpointArgs, RunnerCheckpoint},
+    progress::{Progress, ProgressObserver},
+    table::{ratio, FormatArgs, Table},
+    BoardArgs, LimitArgs, RuleArgs, SeedArgs, TeamArgs,
+};
+use chain_reaction::{
+    from_spec, GameRecord, Limits, Player, Rules, RunObserver, RunReport, Runner, Teams,
+};
+use serde_json::{json, Value};
+use std::{
+    process,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    board: BoardArgs,
+
+    /// Strategies in turn order, e.g. `play_randomly form_chains`
+    #[arg(required = true)]
+    players: Vec<String>,
+
+    /// Number of games to play
+    #[arg(long, default_value_t = 100)]
+    games: u32,
+
+    #[command(flatten)]
+    seed: SeedArgs,
+
+    #[command(flatten)]
+    rules: RuleArgs,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    teams: TeamArgs,
+
+    /// Number of threads to split the games across
+    #[arg(long, default_value_t = 1)]
+    threads: u32,
+
+    #[command(flatten)]
+    checkpoint: CheckpointArgs,
+
+    #[command(flatten)]
+    format: FormatArgs,
+}
+
+pub fn build_players(names: &[String]) -> Result<Vec<Box<dyn Player>>, &'static str> {
+    names.iter().map(|name| from_spec(name)).collect()
+}
+
+/// Board, players and rules shared by every simulated game.
+pub struct GameConfig<'a> {
+    pub width: usize,
+    pub height: usize,
+    /// Strategy of every player in turn order.
+    pub names: &'a [String],
+    pub rules: Rules,
+    pub limits: Limits,
+    pub teams: Teams,
+}
+
+/// Where `simulate` keeps how far its runners got.
+#[derive(Clone, Copy)]
+pub struct Checkpointing<'a> {
+    pub checkpoint: &'a Checkpoint,
+    /// Index of the first runner in the checkpoint, so that one checkpoint
+    /// can cover several simulations.
+    pub first_runner: usize,
+    /// Time between saves.
+    pub every: Duration,
+}
+
+/// Plays `games` games, splitting them evenly across `threads` runners. Each
+/// runner is seeded with `seed` offset by its index, so results only depend
+/// on the seed and the thread count.
+///
+/// With `progress`, finished games are counted in it, a progress bar is
+/// drawn while the games run, and cancelling it stops every runner before
+/// its next move. The report then only covers the games that finished.
+///
+/// With `checkpointing`, runners start from where its checkpoint says they
+/// got to, and it is updated after every game and saved every so often.
+pub fn simulate(
+    config: &GameConfig,
+    games: u32,
+    seed: u64,
+    threads: u32,
+    progress: Option<&Progress>,
+    checkpointing: Option<Checkpointing>,
+) -> Result<RunReport, String> {
+    let threads = threads.clamp(1, games.max(1));
+
+    let (results, saved): (Vec<_>, _) = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|i| {
+                let share = games / threads + u32::from(i < games % threads);
+                scope.spawn(move || {
+                    let players = build_players(config.names)?;
+                    let mut runner = Runner::new(config.width, config.height, players)?;
+                    runner.seed(seed.wrapping_add(u64::from(i)));
+                    runner.set_rules(config.rules)?;
+                    runner.set_limits(config.limits);
+                    runner.set_teams(config.teams.clone())?;
+
+                    let mut report = RunReport::new(&config.teams);
+                    let mut finished = 0;
+                    let slot = checkpointing.map(|c| (c.checkpoint, c.first_runner + i as usize));
+                    if let Some(saved) = slot.and_then(|(c, index)| c.runner(index)) {
+                        runner.load_state(&saved.state)?;
+                        report = saved.report;
+                        finished = saved.games;
+                    }
+
+                    let mut observer: Box<dyn RunObserver> = match progress {
+                        Some(progress) => Box::new(ProgressObserver(progress)),
+                        None => Box::new(()),
+                    };
+                    // Games are played one at a time so that the checkpoint
+                    // always holds the state between two games.
+                    while finished < share {
+                        let game = runner.run_observed(1, &mut *observer)?;
+                        if game.games() == 0 {
+                            break;
+                        }
+                        report.merge(&game);
+                        finished += 1;
+
+                        if let Some((checkpoint, index)) = slot {
+                            let saved = RunnerCheckpoint {
+                                games: finished,
+                                report: report.clone(),
+                                state: runner.save_state(),
+                            };
+                            checkpoint.update(index, saved);
+                        }
+                    }
+                    Ok(report)
+                })
+            })
+            .collect();
+
+        let mut saved = Ok(());
+        let mut saved_at = Instant::now();
+        if progress.is_some() || checkpointing.is_some() {
+            while !handles.iter().all(|handle| handle.is_finished()) {
+                if let Some(progress) = progress {
+                    progress.draw();
+                }
+                if let Some(Checkpointing {
+                    checkpoint, every, ..
+                }) = checkpointing
+                {
+                    if saved.is_ok() && saved_at.elapsed() >= every {
+                        saved = checkpoint.save();
+                        saved_at = Instant::now();
+                    }
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+            if let Some(progress) = progress {
+                progress.clear();
+            }
+        }
+
+        let results = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or(Err("Simulation thread panicked.")))
+            .collect();
+        (results, saved)
+    });
+
+    if let Some(Checkpointing { checkpoint, .. }) = checkpointing {
+        saved?;
+        checkpoint.save()?;
+    }
+
+    let mut report = RunReport::new(&config.teams);
+    for result in results {
+        report.merge(&result?);
+    }
+
+    Ok(report)
+}
+
+pub fn run(args: Args) -> Result<(), String> {
+    let resumed = args.checkpoint.resumed()?;
+    let seed = args.seed.resumed_seed(resumed.as_ref())?;
+    let config = GameConfig {
+        width: args.board.width,
+        height: args.board.height,
+        names: &args.players,
+        rules: args.rules.rules(),
+        limits: args.limits.limits(),
+        teams: args.teams.teams(args.players.len()),
+    };
+
+    let settings = json!({
+        "game": GameRecord {
+            width: config.width,
+            height: config.height,
+            players: config.names.len(),
+            rules: config.rules,
+            capacity: None,
+            limits: config.limits,
+            teams: config.teams.clone(),
+            moves: Vec::new(),
+            outcome: None,
+        }
+        .to_string(),
+        "players": config.names,
+        "games": args.games,
+        "threads": args.threads,
+        "seed": seed,
+    });
+    let checkpoint = args.checkpoint.open(resumed, settings)?;
+    let finished = checkpoint.as_ref().map_or(0, Checkpoint::games);
+
+    // The first Ctrl-C lets the games in progress stop at their next move
+    // so that the games finished so far are still reported. A second one
+    // exits straight away.
+    let progress = Arc::new(Progress::new(args.games, finished));
+    let interrupted = Arc::clone(&progress);
+    ctrlc::set_handler(move || {
+        if interrupted.is_cancelled() {
+            process::exit(130);
+        }
+        interrupted.cancel();
+    })
+    .map_err(|err| format!("Could not handle Ctrl-C: {}", err))?;
+
+    let now = Instant::now();
+    let report = simulate(
+        &config,
+        args.games,
+        seed,
+        args.threads,
+        Some(&progress),
+        checkpoint.as_ref().map(|checkpoint| Checkpointing {
+            checkpoint,
+            first_runner: 0,
+            every: args.checkpoint.every(),
+        }),
+    )?;
+    let elapsed = checkpoint
+        .as_ref()
+        .map_or_else(|| now.elapsed(), Checkpoint::elapsed);
+    let games = report.games() as u64;
+    if progress.is_cancelled() {
+        eprintln!("Stopped after {} of {} games.", games, args.games);
+        if args.checkpoint.file.is_some() {
+            eprintln!("Run the same command with --resume to continue.");
+        }
+    }
+
+    let mut table = Table::new(["player", "strategy", "wins", "win_rate"]);
+    for (player, (name, &wins)) in args.players.iter().zip(&report.wins).enumerate() {
+        table.push(vec![
+            Value::from(player),
+            Value::from(name.as_str()),
+            Value::from(wins),
+            ratio(wins as u64, games),
+        ]);
+    }
+    if !config.teams.is_solo() {
+        for (team, &wins) in report.team_wins.iter().enumerate() {
+            table.push(vec![
+                Value::Null,
+                Value::from(format!("team {}", team)),
+                Value::from(wins),
+                ratio(wins as u64, games),
+            ]);
+        }
+    }
+    for (label, count) in [("draw", report.draws), ("aborted", report.aborted)] {
+        if count > 0 {
+            table.push(vec![
+                Value::Null,
+                Value::from(label),
+                Value::from(count),
+                ratio(count as u64, games),
+            ]);
+        }
+    }
+
+    if args.format.is_human() {
+        println!(
+            "{} games on a {}x{} board in {}ms (seed {})\n",
+            games,
+            args.board.width,
+            args.board.height,
+            elapsed.as_millis(),
+            seed
+        );
+    }
+    table.print(&args.format);
+
+    Ok(())
+}
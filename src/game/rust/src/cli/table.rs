@@ -0,0 +1,162 @@
+use serde_json::{Map, Value};
+
+#[derive(clap::Args)]
+pub struct FormatArgs {
+    /// Print results as JSON
+    #[arg(long, conflicts_with = "csv")]
+    pub json: bool,
+
+    /// Print results as CSV
+    #[arg(long)]
+    pub csv: bool,
+}
+
+impl FormatArgs {
+    pub fn is_human(&self) -> bool {
+        !self.json && !self.csv
+    }
+}
+
+/// Rounds a ratio to four decimal places so that tables stay readable.
+pub fn ratio(numerator: u64, denominator: u64) -> Value {
+    if denominator == 0 {
+        Value::from(0.0)
+    } else {
+        let value = numerator as f64 / denominator as f64;
+        Value::from((value * 10000.0).round() / 10000.0)
+    }
+}
+
+pub struct Table {
+    columns: Vec<String>,
+    rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    pub fn new<I, S>(columns: I) -> Table
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Table {
+            columns: columns.into_iter().map(Into::into).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<Value>) {
+        debug_assert_eq!(row.len(), self.columns.len());
+        self.rows.push(row);
+    }
+
+    pub fn print(&self, format: &FormatArgs) {
+        if format.json {
+            println!("{}", self.to_json());
+        } else if format.csv {
+            print!("{}", self.to_csv());
+        } else {
+            print!("{}", self.to_human());
+        }
+    }
+
+    fn to_json(&self) -> String {
+        let rows: Vec<_> = self
+            .rows
+            .iter()
+            .map(|row| {
+                let object: Map<_, _> = self
+                    .columns
+                    .iter()
+                    .cloned()
+                    .zip(row.iter().cloned())
+                    .collect();
+                Value::Object(object)
+            })
+            .collect();
+
+        serde_json::to_string_pretty(&rows).unwrap_or_default()
+    }
+
+    fn to_csv(&self) -> String {
+        let mut out = csv_line(self.columns.iter().map(String::as_str));
+        for row in &self.rows {
+            let cells: Vec<_> = row.iter().map(plain).collect();
+            out += &csv_line(cells.iter().map(String::as_str));
+        }
+        out
+    }
+
+    fn to_human(&self) -> String {
+        let cells: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(plain).collect())
+            .collect();
+
+        let widths: Vec<_> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                cells
+                    .iter()
+                    .map(|row| row[i].len())
+                    .chain(Some(column.len()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let mut out = String::new();
+        let header: Vec<_> = self
+            .columns
+            .iter()
+            .zip(&widths)
+            .map(|(column, &width)| format!("{:<width$}", column, width = width))
+            .collect();
+        out += header.join("  ").trim_end();
+        out.push('\n');
+
+        let rule: Vec<_> = widths.iter().map(|&width| "-".repeat(width)).collect();
+        out += &rule.join("  ");
+        out.push('\n');
+
+        for (row, values) in cells.iter().zip(&self.rows) {
+            let line: Vec<_> = row
+                .iter()
+                .zip(values)
+                .zip(&widths)
+                .map(|((cell, value), &width)| match value {
+                    Value::Number(_) => format!("{:>width$}", cell, width = width),
+                    _ => format!("{:<width$}", cell, width = width),
+                })
+                .collect();
+            out += line.join("  ").trim_end();
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+fn plain(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn csv_line<'a>(cells: impl Iterator<Item = &'a str>) -> String {
+    let cells: Vec<_> = cells
+        .map(|cell| {
+            if cell.contains([',', '"', '\n']) {
+                format!("\"{}\"", cell.replace('"', "\"\""))
+            } else {
+                cell.to_string()
+            }
+        })
+        .collect();
+
+    cells.join(",") + "\n"
+}
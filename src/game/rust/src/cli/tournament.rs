@@ -0,0 +1,151 @@
+use super::{
+    checkpoint::CheckpointArgs,
+    simulate::{simulate, Checkpointing, GameConfig},
+    table::{ratio, FormatArgs, Table},
+    BoardArgs, LimitArgs, SeedArgs,
+};
+use chain_reaction::{GameRecord, Rules, Teams};
+use serde_json::{json, Value};
+
+#[derive(clap::Args)]
+pub struct Args {
+    #[command(flatten)]
+    board: BoardArgs,
+
+    /// Strategies taking part in the tournament
+    #[arg(required = true, num_args = 2..)]
+    strategies: Vec<String>,
+
+    /// Number of games per pairing. Each strategy moves first in half of them.
+    #[arg(long, default_value_t = 100)]
+    games: u32,
+
+    #[command(flatten)]
+    seed: SeedArgs,
+
+    #[command(flatten)]
+    limits: LimitArgs,
+
+    #[command(flatten)]
+    checkpoint: CheckpointArgs,
+
+    #[command(flatten)]
+    format: FormatArgs,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Standing {
+    games: u64,
+    wins: u64,
+    draws: u64,
+    /// Games cut off by `--max-plies`.
+    aborted: u64,
+}
+
+/// Plays every pair of strategies against each other on a two player board.
+///
+/// Each half of a pairing is a simulation of its own, kept in the checkpoint
+/// as one runner. Resumed halves that had already finished are taken from
+/// the checkpoint without playing them again.
+pub fn run(args: Args) -> Result<(), String> {
+    let resumed = args.checkpoint.resumed()?;
+    let seed = args.seed.resumed_seed(resumed.as_ref())?;
+    let settings = json!({
+        "game": GameRecord {
+            width: args.board.width,
+            height: args.board.height,
+            players: 2,
+            rules: Rules::default(),
+            capacity: None,
+            limits: args.limits.limits(),
+            teams: Teams::solo(2),
+            moves: Vec::new(),
+            outcome: None,
+        }
+        .to_string(),
+        "strategies": args.strategies,
+        "games": args.games,
+        "seed": seed,
+    });
+    let checkpoint = args.checkpoint.open(resumed, settings)?;
+
+    let count = args.strategies.len();
+    let mut standings = vec![Standing::default(); count];
+    let mut round = 0;
+
+    for a in 0..count {
+        for b in a + 1..count {
+            let halves = [(a, b, args.games - args.games / 2), (b, a, args.games / 2)];
+
+            for &(first, second, games) in &halves {
+                let names = [
+                    args.strategies[first].clone(),
+                    args.strategies[second].clone(),
+                ];
+                let config = GameConfig {
+                    width: args.board.width,
+                    height: args.board.height,
+                    names: &names,
+                    rules: Rules::default(),
+                    limits: args.limits.limits(),
+                    teams: Teams::solo(2),
+                };
+                let checkpointing = checkpoint.as_ref().map(|checkpoint| Checkpointing {
+                    checkpoint,
+                    first_runner: round as usize,
+                    every: args.checkpoint.every(),
+                });
+                let report = simulate(
+                    &config,
+                    games,
+                    seed.wrapping_add(round),
+                    1,
+                    None,
+                    checkpointing,
+                )?;
+                round += 1;
+
+                for (&player, &wins) in [first, second].iter().zip(&report.wins) {
+                    standings[player].games += u64::from(games);
+                    standings[player].wins += wins as u64;
+                    standings[player].draws += report.draws as u64;
+                    standings[player].aborted += report.aborted as u64;
+                }
+            }
+        }
+    }
+
+    let mut order: Vec<_> = (0..count).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(standings[i].wins));
+
+    let mut table = Table::new([
+        "rank", "strategy", "games", "wins", "draws", "aborted", "win_rate",
+    ]);
+    for (rank, &i) in order.iter().enumerate() {
+        let Standing {
+            games,
+            wins,
+            draws,
+            aborted,
+        } = standings[i];
+        table.push(vec![
+            Value::from(rank + 1),
+            Value::from(args.strategies[i].as_str()),
+            Value::from(games),
+            Value::from(wins),
+            Value::from(draws),
+            Value::from(aborted),
+            ratio(wins, games),
+        ]);
+    }
+
+    if args.format.is_human() {
+        println!(
+            "Round-robin on a {}x{} board, {} games per pairing (seed {})\n",
+            args.board.width, args.board.height, args.games, seed
+        );
+    }
+    table.print(&args.format);
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+//! A corpus of games played out by this engine, each stored along with the
+//! position and result it ends in, so that other implementations of the
+//! rules can be checked against it without running any Rust.
+//!
+//! A case is a game record in the usual notation, ending with its `result`
+//! line, followed by the final position. Only the classic rules are used so
+//! that every implementation can replay the games.
+
+use crate::{
+    notation::{format_position, parse_position},
+    players::from_name,
+    runner::Runner,
+    ChainReaction, GameRecord, Player,
+};
+use std::fmt;
+
+/// Board width, height and the strategy of every player for each group of
+/// cases in the corpus. Boards stay within the sizes the web app offers.
+const CORPUS: [(usize, usize, &[&str]); 8] = [
+    (2, 2, &["play_randomly", "play_randomly"]),
+    (2, 5, &["play_randomly", "form_chains"]),
+    (7, 3, &["avoid_others", "form_chains", "play_randomly"]),
+    (3, 3, &["avoid_others", "play_randomly"]),
+    (5, 5, &["play_randomly", "form_chains"]),
+    (8, 6, &["form_chains", "avoid_others"]),
+    (4, 4, &["play_randomly", "form_chains", "avoid_others"]),
+    (
+        6,
+        6,
+        &[
+            "play_randomly",
+            "form_chains",
+            "play_randomly",
+            "avoid_others",
+        ],
+    ),
+];
+
+/// Games played for every group of the corpus.
+const GAMES_PER_GROUP: u64 = 4;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConformanceCase {
+    pub record: GameRecord,
+    /// Final position in notation.
+    pub position: String,
+}
+
+impl ConformanceCase {
+    pub fn from_game(game: &ChainReaction) -> ConformanceCase {
+        ConformanceCase {
+            record: GameRecord::from_game(game),
+            position: format_position(game),
+        }
+    }
+
+    pub fn parse(input: &str) -> Result<ConformanceCase, &'static str> {
+        let lines: Vec<_> = input.lines().collect();
+        let split = lines
+            .iter()
+            .position(|line| line.trim_start().starts_with("position"))
+            .ok_or("Conformance case has no final position.")?;
+
+        let record = GameRecord::parse(&lines[..split].join("\n"))?;
+        let position = format_position(&parse_position(&lines[split..].join("\n"))?);
+        Ok(ConformanceCase { record, position })
+    }
+
+    /// Replays the moves and checks that they lead to the stored position
+    /// and result.
+    pub fn verify(&self) -> Result<(), &'static str> {
+        let game = self.record.replay()?;
+        if format_position(&game) != self.position {
+            return Err("Moves lead to a different position.");
+        }
+        if game.outcome() != self.record.outcome {
+            return Err("Moves lead to a different result.");
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ConformanceCase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}\n\n{}", self.record, self.position)
+    }
+}
+
+/// Plays the games of the corpus, named after their board, player count
+/// and number within their group. The same seed always gives the same
+/// corpus.
+pub fn generate_corpus(seed: u64) -> Result<Vec<(String, ConformanceCase)>, &'static str> {
+    let mut cases = Vec::new();
+
+    for (group, &(width, height, strategies)) in CORPUS.iter().enumerate() {
+        let players = strategies
+            .iter()
+            .map(|name| from_name(name))
+            .collect::<Result<Vec<Box<dyn Player>>, _>>()?;
+        let mut runner = Runner::new(width, height, players)?;
+
+        for game in 0..GAMES_PER_GROUP {
+            runner.seed(seed ^ ((group as u64) << 32) ^ game);
+            runner.run(1)?;
+
+            let name = format!("{}x{}-{}p-{}", width, height, strategies.len(), game);
+            cases.push((name, ConformanceCase::from_game(runner.game())));
+        }
+    }
+
+    Ok(cases)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cases_round_trip_and_catch_changes() {
+        let cases = generate_corpus(1).unwrap();
+        assert_eq!(cases.len(), CORPUS.len() * GAMES_PER_GROUP as usize);
+
+        for (_, case) in &cases {
+            assert!(case.record.outcome.is_some());
+            let parsed = ConformanceCase::parse(&case.to_string()).unwrap();
+            assert_eq!(&parsed, case);
+            parsed.verify().unwrap();
+        }
+
+        let (_, case) = &cases[cases.len() - 1];
+        let mut changed = case.clone();
+        changed.record.moves.pop();
+        assert!(changed.verify().is_err());
+    }
+}
@@ -0,0 +1,241 @@
+use crate::array2d::{Array2D, Pos};
+use crate::chain_reaction::{ChainReaction, Move, Outcome};
+use crate::runner::{RunEvent, RunObserver};
+use std::collections::BTreeMap;
+
+/// Per-cell statistics accumulated over many games, collected by observing
+/// the run that plays them.
+#[derive(Clone)]
+pub struct Heatmap {
+    width: usize,
+    height: usize,
+    /// Strategy of every player, in turn order.
+    strategies: Vec<String>,
+    games: u32,
+
+    /// Cells chosen by each strategy, over all the seats it played.
+    chosen: BTreeMap<String, Array2D<u32>>,
+    moves: BTreeMap<String, u32>,
+    exploded: Array2D<u32>,
+    captured: Array2D<u32>,
+    openings: Array2D<u32>,
+    opening_wins: Array2D<u32>,
+    /// First move of the game in progress.
+    opening: Option<Move>,
+}
+
+impl Heatmap {
+    /// A heatmap for games between `strategies`, one per player in turn
+    /// order. Players with the same strategy share their statistics.
+    pub fn new(
+        width: usize,
+        height: usize,
+        strategies: Vec<String>,
+    ) -> Result<Heatmap, &'static str> {
+        // Borrow the engine's validation of the board dimensions.
+        ChainReaction::new(width, height, strategies.len())?;
+
+        let zeros = Array2D::new(0, Pos(width, height));
+        Ok(Heatmap {
+            width,
+            height,
+            chosen: strategies
+                .iter()
+                .map(|strategy| (strategy.clone(), zeros.clone()))
+                .collect(),
+            moves: strategies
+                .iter()
+                .map(|strategy| (strategy.clone(), 0))
+                .collect(),
+            strategies,
+            games: 0,
+
+            exploded: zeros.clone(),
+            captured: zeros.clone(),
+            openings: zeros.clone(),
+            opening_wins: zeros,
+            opening: None,
+        })
+    }
+
+    pub fn games(&self) -> u32 {
+        self.games
+    }
+
+    fn map(
+        &self,
+        f: impl Fn(Pos) -> Result<f64, &'static str>,
+    ) -> Result<Array2D<f64>, &'static str> {
+        let mut result = Array2D::new(0.0, Pos(self.width, self.height));
+        for y in 0..self.height {
+            for x in 0..self.width {
+                *result.get_mut(&Pos(x, y))? = f(Pos(x, y))?;
+            }
+        }
+        Ok(result)
+    }
+
+    fn per_game(&self, counts: &Array2D<u32>) -> Result<Array2D<f64>, &'static str> {
+        let games = f64::from(self.games.max(1));
+        self.map(|pos| Ok(f64::from(*counts.get(&pos)?) / games))
+    }
+
+    /// Share of the moves made by `strategy` that went to each cell.
+    pub fn choices(&self, strategy: &str) -> Result<Array2D<f64>, &'static str> {
+        let chosen = self.chosen.get(strategy).ok_or("Unknown strategy.")?;
+        let moves = f64::from(self.moves[strategy].max(1));
+        self.map(|pos| Ok(f64::from(*chosen.get(&pos)?) / moves))
+    }
+
+    /// Average number of times each cell exploded per game.
+    pub fn explosions(&self) -> Result<Array2D<f64>, &'static str> {
+        self.per_game(&self.exploded)
+    }
+
+    /// Average number of times each cell changed hands per game.
+    pub fn captures(&self) -> Result<Array2D<f64>, &'static str> {
+        self.per_game(&self.captured)
+    }
+
+    /// How often the first player went on to win after opening on each
+    /// cell. Cells that were never opened on are NaN.
+    pub fn opening_win_rate(&self) -> Result<Array2D<f64>, &'static str> {
+        self.map(|pos| {
+            let games = *self.openings.get(&pos)?;
+            let wins = *self.opening_wins.get(&pos)?;
+            Ok(if games == 0 {
+                f64::NAN
+            } else {
+                f64::from(wins) / f64::from(games)
+            })
+        })
+    }
+}
+
+fn count(counts: &mut Array2D<u32>, pos: &Pos) {
+    if let Ok(count) = counts.get_mut(pos) {
+        *count += 1;
+    }
+}
+
+/// Moves of a game that a stopped run drops partway through still count,
+/// but the game itself does not.
+impl RunObserver for Heatmap {
+    fn observe(&mut self, event: &RunEvent, game: &ChainReaction) {
+        if game.width() != self.width || game.height() != self.height {
+            return;
+        }
+
+        match *event {
+            RunEvent::GameStarted { .. } => self.opening = None,
+            RunEvent::MoveMade { player, pos } => {
+                if let Some(strategy) = self.strategies.get(player) {
+                    if let Some(chosen) = self.chosen.get_mut(strategy) {
+                        count(chosen, &pos);
+                    }
+                    if let Some(moves) = self.moves.get_mut(strategy) {
+                        *moves += 1;
+                    }
+                }
+                if self.opening.is_none() {
+                    self.opening = Some(Move { player, pos });
+                }
+            }
+            RunEvent::Exploded(explosion) => {
+                for pos in explosion.waves.iter().flatten() {
+                    count(&mut self.exploded, pos);
+                }
+                for pos in &explosion.captured {
+                    count(&mut self.captured, pos);
+                }
+            }
+            RunEvent::PlayerEliminated(_) => {}
+            RunEvent::GameFinished { outcome, .. } => {
+                if let Some(first) = self.opening.take() {
+                    count(&mut self.openings, &first.pos);
+                    if outcome == Outcome::Win(first.player) {
+                        count(&mut self.opening_wins, &first.pos);
+                    }
+                }
+                self.games += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{players::from_name, Runner, StatsObserver};
+
+    #[test]
+    fn counts_moves_explosions_and_captures() {
+        let game = ChainReaction::new(2, 2, 2).unwrap();
+        let mut heatmap = Heatmap::new(2, 2, vec!["a".into(), "b".into()]).unwrap();
+        heatmap.observe(&RunEvent::GameStarted { game: 1 }, &game);
+        for &pos in &[Pos(0, 0), Pos(1, 0), Pos(0, 0)] {
+            let player = game.current_player();
+            let explosion = game.place_traced(&pos).unwrap();
+            heatmap.observe(&RunEvent::MoveMade { player, pos }, &game);
+            heatmap.observe(&RunEvent::Exploded(&explosion), &game);
+        }
+        let report = Default::default();
+        let outcome = game.outcome().unwrap();
+        heatmap.observe(
+            &RunEvent::GameFinished {
+                game: 1,
+                outcome,
+                report: &report,
+            },
+            &game,
+        );
+
+        assert_eq!(heatmap.games(), 1);
+        assert_eq!(heatmap.choices("a").unwrap().get(&Pos(0, 0)), Ok(&1.0));
+        assert_eq!(heatmap.choices("b").unwrap().get(&Pos(1, 0)), Ok(&1.0));
+        assert_eq!(heatmap.explosions().unwrap().get(&Pos(0, 0)), Ok(&1.0));
+        assert_eq!(heatmap.captures().unwrap().get(&Pos(1, 0)), Ok(&1.0));
+        assert_eq!(
+            heatmap.opening_win_rate().unwrap().get(&Pos(0, 0)),
+            Ok(&1.0)
+        );
+        assert!(heatmap
+            .opening_win_rate()
+            .unwrap()
+            .get(&Pos(1, 1))
+            .unwrap()
+            .is_nan());
+    }
+
+    #[test]
+    fn merges_seats_with_the_same_strategy() {
+        let names = ["form_chains", "play_randomly", "form_chains"];
+        let players = names.iter().map(|name| from_name(name).unwrap()).collect();
+        let mut runner = Runner::new(4, 4, players).unwrap();
+        runner.seed(5);
+
+        let strategies = names.iter().map(|name| name.to_string()).collect();
+        let mut heatmap = Heatmap::new(4, 4, strategies).unwrap();
+        let mut stats = StatsObserver::new();
+        runner
+            .run_observed(20, &mut (&mut heatmap, &mut stats))
+            .unwrap();
+
+        assert_eq!(heatmap.games(), 20);
+        for name in &names[..2] {
+            let total: f64 = heatmap.choices(name).unwrap().iter().sum();
+            assert!((total - 1.0).abs() < 1e-9);
+        }
+        let moves: Vec<_> = heatmap
+            .moves
+            .values()
+            .map(|&moves| u64::from(moves))
+            .collect();
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves.iter().sum::<u64>(), stats.moves);
+        assert_eq!(
+            heatmap.choices("avoid_others").err(),
+            Some("Unknown strategy.")
+        );
+    }
+}
@@ -1,21 +1,10 @@
-use chain_reaction::{PlayRandomly, Player, Runner};
-use std::time::Instant;
+use std::process;
 
-fn main() -> Result<(), &'static str> {
-    let width = 3;
-    let height = 3;
-    let players: Vec<Box<dyn Player>> = vec![
-        Box::new(PlayRandomly {}),
-        Box::new(PlayRandomly {}),
-        Box::new(PlayRandomly {}),
-    ];
+mod cli;
 
-    let now = Instant::now();
-    let mut runner = Runner::new(width, height, players, None, None)?;
-    let tally = runner.run(100)?;
-    println!("Elapsed: {}ms", now.elapsed().as_millis());
-
-    println!("Winner: {:?}", tally);
-
-    Ok(())
+fn main() {
+    if let Err(err) = cli::run() {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
 }
@@ -0,0 +1,511 @@
+//! Plain text formats for positions and game records.
+//!
+//! A position starts with a `position <width> <height> <players> <current>
+//! <moves>` header followed by one line per row. Each field is either `.`
+//! for an empty field or `<count>:<owner>`.
+//!
+//! Both formats take an optional `rules <offset> <keep|discard> <bfs|dfs>
+//! <own|any>` line after the header for games that do not use the classic
+//! rules. Boards with bespoke capacities follow it with a `capacity` line and
+//! one row of capacities per board row.
+//!
+//! A game record starts with a `game <width> <height> <players>` header
+//! followed by one `<player> <x>,<y>` line per move. Games played under
+//! limits add a `limits <waves> <plies>` line after the header, with `-` for
+//! no limit. Team games add a `teams <team>... [convert]` line listing the
+//! team of every player, followed by `convert` if explosions take over
+//! allied cells. Finished games end with a `result <winner|draw|aborted>`
+//! line.
+//!
+//! Blank lines and lines starting with `#` are ignored in both formats.
+
+use crate::array2d::{Array2D, Pos};
+use crate::chain_reaction::{
+    check_size, ChainReaction, Field, FieldData, Limits, Move, Outcome, Resolution, Rules, Teams,
+};
+use std::{fmt, str::FromStr};
+
+pub(crate) fn parse_number<T: FromStr>(input: &str) -> Result<T, &'static str> {
+    input.parse().map_err(|_| "Invalid number.")
+}
+
+/// Parses a cell written as `<x>,<y>`, as in the moves of a game record.
+pub fn parse_pos(input: &str) -> Result<Pos, &'static str> {
+    let (x, y) = input.split_once(',').ok_or("Invalid position.")?;
+    Ok(Pos(parse_number(x)?, parse_number(y)?))
+}
+
+fn content_lines(input: &str) -> impl Iterator<Item = &str> {
+    input
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+}
+
+fn parse_field(input: &str) -> Result<Field, &'static str> {
+    if input == "." {
+        return Ok(Field::Empty);
+    }
+
+    let (count, owner) = input.split_once(':').ok_or("Invalid field.")?;
+    Ok(Field::Owned(FieldData {
+        owner: parse_number(owner)?,
+        count: parse_number(count)?,
+    }))
+}
+
+/// Reads `height` rows of `width` whitespace separated items.
+fn parse_rows<'a, T>(
+    lines: &mut impl Iterator<Item = &'a str>,
+    width: usize,
+    height: usize,
+    parse: impl Fn(&str) -> Result<T, &'static str>,
+) -> Result<Vec<T>, &'static str> {
+    let mut items = Vec::new();
+    for _ in 0..height {
+        let row = lines
+            .next()
+            .ok_or("Too few rows.")?
+            .split_whitespace()
+            .map(&parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if row.len() != width {
+            return Err("Row length does not match the board width.");
+        }
+        items.extend(row);
+    }
+
+    Ok(items)
+}
+
+fn parse_capacity<'a>(
+    lines: &mut impl Iterator<Item = &'a str>,
+    width: usize,
+    height: usize,
+) -> Result<Array2D<u32>, &'static str> {
+    let values = parse_rows(lines, width, height, parse_number)?;
+    let mut capacity = Array2D::new(0, Pos(width, height));
+    for (i, value) in values.into_iter().enumerate() {
+        *capacity.get_mut(&Pos(i % width, i / width))? = value;
+    }
+    Ok(capacity)
+}
+
+fn format_capacity(capacity: &Array2D<u32>) -> String {
+    let mut out = "capacity".to_string();
+    for y in 0..capacity.height() {
+        out.push('\n');
+        let row: Vec<_> = (0..capacity.width())
+            .map(|x| capacity.get(&Pos(x, y)).map_or(0, |&c| c).to_string())
+            .collect();
+        out += &row.join(" ");
+    }
+    out
+}
+
+fn parse_rules(fields: &[&str]) -> Result<Rules, &'static str> {
+    match *fields {
+        ["rules", offset, excess, resolution, placement] => Ok(Rules {
+            capacity_offset: parse_number(offset)?,
+            keep_excess: match excess {
+                "keep" => true,
+                "discard" => false,
+                _ => return Err("Invalid rules."),
+            },
+            resolution: match resolution {
+                "bfs" => Resolution::BreadthFirst,
+                "dfs" => Resolution::DepthFirst,
+                _ => return Err("Invalid rules."),
+            },
+            place_on_opponents: match placement {
+                "any" => true,
+                "own" => false,
+                _ => return Err("Invalid rules."),
+            },
+        }),
+        _ => Err("Invalid rules."),
+    }
+}
+
+fn format_rules(rules: Rules) -> String {
+    format!(
+        "rules {} {} {} {}",
+        rules.capacity_offset,
+        if rules.keep_excess { "keep" } else { "discard" },
+        match rules.resolution {
+            Resolution::BreadthFirst => "bfs",
+            Resolution::DepthFirst => "dfs",
+        },
+        if rules.place_on_opponents {
+            "any"
+        } else {
+            "own"
+        }
+    )
+}
+
+pub fn format_position(game: &ChainReaction) -> String {
+    let mut out = format!(
+        "position {} {} {} {} {}",
+        game.width(),
+        game.height(),
+        game.players(),
+        game.current_player(),
+        game.move_count()
+    );
+    if game.rules() != Rules::default() {
+        out.push('\n');
+        out += &format_rules(game.rules());
+    }
+    if let Some(capacity) = game.custom_capacity() {
+        out.push('\n');
+        out += &format_capacity(capacity);
+    }
+
+    for y in 0..game.height() {
+        out.push('\n');
+        for x in 0..game.width() {
+            if x != 0 {
+                out.push(' ');
+            }
+            match game.grid.get(&Pos(x, y)).map(|c| c.get()) {
+                Ok(Field::Owned(FieldData { owner, count })) => {
+                    out += &format!("{}:{}", count, owner)
+                }
+                _ => out.push('.'),
+            }
+        }
+    }
+
+    out
+}
+
+pub fn parse_position(input: &str) -> Result<ChainReaction, &'static str> {
+    let mut lines = content_lines(input).peekable();
+
+    let header: Vec<_> = lines
+        .next()
+        .ok_or("Position is empty.")?
+        .split_whitespace()
+        .collect();
+
+    let (width, height, players, current, moves) = match header.as_slice() {
+        ["position", width, height, players, current, moves] => (
+            parse_number(width)?,
+            parse_number(height)?,
+            parse_number(players)?,
+            parse_number(current)?,
+            parse_number(moves)?,
+        ),
+        _ => return Err("Invalid position header."),
+    };
+
+    let mut rules = Rules::default();
+    if let Some(line) = lines.peek().filter(|line| line.starts_with("rules")) {
+        rules = parse_rules(&line.split_whitespace().collect::<Vec<_>>())?;
+        lines.next();
+    }
+
+    let mut game = ChainReaction::with_rules(width, height, players, rules)?;
+    if lines.peek() == Some(&"capacity") {
+        lines.next();
+        game.set_capacity(parse_capacity(&mut lines, width, height)?)?;
+    }
+
+    let cells = parse_rows(&mut lines, width, height, parse_field)?;
+    if lines.next().is_some() {
+        return Err("Position has too many rows.");
+    }
+
+    game.load_position(current, moves, &cells)?;
+    Ok(game)
+}
+
+fn parse_limit(input: &str) -> Result<Option<u32>, &'static str> {
+    match input {
+        "-" => Ok(None),
+        _ => parse_number(input).map(Some),
+    }
+}
+
+fn parse_outcome(input: &str) -> Result<Outcome, &'static str> {
+    match input {
+        "draw" => Ok(Outcome::Draw),
+        "aborted" => Ok(Outcome::Aborted),
+        _ => parse_number(input).map(Outcome::Win),
+    }
+}
+
+fn format_limit(limit: Option<u32>) -> String {
+    limit.map_or_else(|| "-".to_string(), |limit| limit.to_string())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameRecord {
+    pub width: usize,
+    pub height: usize,
+    pub players: usize,
+    pub rules: Rules,
+    /// Capacities of the board if they were set by hand.
+    pub capacity: Option<Array2D<u32>>,
+    pub limits: Limits,
+    pub teams: Teams,
+    pub moves: Vec<Move>,
+    pub outcome: Option<Outcome>,
+}
+
+impl GameRecord {
+    pub fn from_game(game: &ChainReaction) -> GameRecord {
+        GameRecord {
+            width: game.width(),
+            height: game.height(),
+            players: game.players(),
+            rules: game.rules(),
+            capacity: game.custom_capacity().cloned(),
+            limits: game.limits(),
+            teams: game.teams().clone(),
+            moves: game.history().clone(),
+            outcome: game.outcome(),
+        }
+    }
+
+    pub fn parse(input: &str) -> Result<GameRecord, &'static str> {
+        let mut lines = content_lines(input).peekable();
+
+        let header: Vec<_> = lines
+            .next()
+            .ok_or("Game record is empty.")?
+            .split_whitespace()
+            .collect();
+
+        let (width, height, players) = match header.as_slice() {
+            ["game", width, height, players] => (
+                parse_number(width)?,
+                parse_number(height)?,
+                parse_number(players)?,
+            ),
+            _ => return Err("Invalid game record header."),
+        };
+        check_size(width, height, players)?;
+
+        let mut rules = Rules::default();
+        let mut capacity = None;
+        let mut limits = Limits::default();
+        let mut teams = Teams::solo(players);
+        while let Some(&line) = lines.peek() {
+            match line.split_whitespace().collect::<Vec<_>>()[..] {
+                ref fields @ ["rules", ..] => rules = parse_rules(fields)?,
+                ["capacity"] => {
+                    lines.next();
+                    capacity = Some(parse_capacity(&mut lines, width, height)?);
+                    continue;
+                }
+                ["limits", waves, plies] => {
+                    limits = Limits {
+                        max_waves: parse_limit(waves)?,
+                        max_plies: parse_limit(plies)?,
+                    };
+                }
+                ["teams", ref rest @ ..] => {
+                    let (assignment, convert_allies) = match rest {
+                        [assignment @ .., "convert"] => (assignment, true),
+                        _ => (rest, false),
+                    };
+                    teams = Teams {
+                        assignment: assignment
+                            .iter()
+                            .map(|team| parse_number(team))
+                            .collect::<Result<_, _>>()?,
+                        convert_allies,
+                    };
+                }
+                _ => break,
+            }
+            lines.next();
+        }
+
+        let mut moves = Vec::new();
+        let mut outcome = None;
+        for line in lines {
+            if outcome.is_some() {
+                return Err("The result must be the last line.");
+            }
+
+            match line.split_whitespace().collect::<Vec<_>>()[..] {
+                ["result", result] => outcome = Some(parse_outcome(result)?),
+                [player, pos] => moves.push(Move {
+                    player: parse_number(player)?,
+                    pos: parse_pos(pos)?,
+                }),
+                _ => return Err("Invalid move."),
+            }
+        }
+
+        Ok(GameRecord {
+            width,
+            height,
+            players,
+            rules,
+            capacity,
+            limits,
+            teams,
+            moves,
+            outcome,
+        })
+    }
+
+    /// Sets up an empty board with the recorded rules, capacities, limits
+    /// and teams.
+    pub fn new_game(&self) -> Result<ChainReaction, &'static str> {
+        let mut game =
+            ChainReaction::with_rules(self.width, self.height, self.players, self.rules)?;
+        if let Some(capacity) = &self.capacity {
+            game.set_capacity(capacity.clone())?;
+        }
+        game.set_limits(self.limits);
+        game.set_teams(self.teams.clone())?;
+        Ok(game)
+    }
+
+    /// Plays the recorded moves on a fresh board.
+    pub fn replay(&self) -> Result<ChainReaction, &'static str> {
+        let game = self.new_game()?;
+        game.play_moves(&self.moves)?;
+        Ok(game)
+    }
+}
+
+impl fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "game {} {} {}", self.width, self.height, self.players)?;
+        if self.rules != Rules::default() {
+            write!(f, "\n{}", format_rules(self.rules))?;
+        }
+        if let Some(capacity) = &self.capacity {
+            write!(f, "\n{}", format_capacity(capacity))?;
+        }
+        if self.limits != Limits::default() {
+            write!(
+                f,
+                "\nlimits {} {}",
+                format_limit(self.limits.max_waves),
+                format_limit(self.limits.max_plies)
+            )?;
+        }
+        if !self.teams.is_solo() || self.teams.convert_allies {
+            write!(f, "\nteams")?;
+            for team in &self.teams.assignment {
+                write!(f, " {}", team)?;
+            }
+            if self.teams.convert_allies {
+                write!(f, " convert")?;
+            }
+        }
+        for Move {
+            player,
+            pos: Pos(x, y),
+        } in &self.moves
+        {
+            write!(f, "\n{} {},{}", player, x, y)?;
+        }
+        match self.outcome {
+            Some(Outcome::Win(winner)) => write!(f, "\nresult {}", winner),
+            Some(Outcome::Draw) => write!(f, "\nresult draw"),
+            Some(Outcome::Aborted) => write!(f, "\nresult aborted"),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn position_round_trip() {
+        let game = ChainReaction::new(3, 2, 2).unwrap();
+        game.place(&Pos(0, 0)).unwrap();
+        game.place(&Pos(1, 1)).unwrap();
+        game.place(&Pos(1, 1)).unwrap_err();
+        game.place(&Pos(2, 1)).unwrap();
+
+        let text = format_position(&game);
+        assert_eq!(text, "position 3 2 2 1 3\n1:0 . .\n. 1:1 1:0");
+        assert_eq!(format_position(&parse_position(&text).unwrap()), text);
+    }
+
+    #[test]
+    fn rejects_overloaded_fields() {
+        assert!(parse_position("position 2 2 2 0 2\n2:0 .\n. .").is_err());
+        assert!(parse_position("position 2 2 2 0 2\n1:2 .\n. .").is_err());
+        assert!(parse_position("position 2 2 2 0 2\n1:0 .").is_err());
+    }
+
+    #[test]
+    fn rejects_oversized_boards() {
+        let huge = "position 3 10442144815179337273 2 1 3\n1:0 . .";
+        assert_eq!(parse_position(huge).err(), Some("Board is too large."));
+        assert_eq!(
+            GameRecord::parse("game 65537 65537 2").err(),
+            Some("Board is too large.")
+        );
+    }
+
+    #[test]
+    fn rejects_too_many_players() {
+        assert_eq!(
+            GameRecord::parse("game 3 3 1000000000000").err(),
+            Some("Too many players.")
+        );
+    }
+
+    #[test]
+    fn rejects_oversized_capacities() {
+        assert_eq!(
+            parse_position("position 1 1 2 0 0\nrules 4294967295 discard bfs own\n.").err(),
+            Some("Capacity is too large.")
+        );
+        let record = GameRecord::parse("game 3 3 2\ncapacity\n1 1 1\n1 9999 1\n1 1 1").unwrap();
+        assert_eq!(record.replay().err(), Some("Capacity is too large."));
+    }
+
+    #[test]
+    fn rejects_teams_past_the_player_count() {
+        let record = GameRecord::parse("game 3 3 2\nteams 0 18446744073709551615").unwrap();
+        assert_eq!(
+            record.replay().err(),
+            Some("Teams must be numbered from 0 without gaps.")
+        );
+    }
+
+    #[test]
+    fn record_round_trip() {
+        let input = "# opening\ngame 3 3 2\n0 1,1\n1 0,0\n0 1,1";
+        let record = GameRecord::parse(input).unwrap();
+        assert_eq!(record.moves.len(), 3);
+
+        let game = record.replay().unwrap();
+        assert_eq!(GameRecord::from_game(&game), record);
+        assert_eq!(GameRecord::parse(&record.to_string()).unwrap(), record);
+    }
+
+    #[test]
+    fn record_keeps_limits_and_result() {
+        let input = "game 2 2 2\nlimits - 3\n0 0,0\n1 1,1\n0 1,0\nresult aborted";
+        let record = GameRecord::parse(input).unwrap();
+        assert_eq!(record.limits.max_plies, Some(3));
+        assert_eq!(record.outcome, Some(Outcome::Aborted));
+        assert_eq!(record.to_string(), input);
+
+        let game = record.replay().unwrap();
+        assert_eq!(GameRecord::from_game(&game), record);
+        assert!(GameRecord::parse("game 2 2 2\nresult 0\n0 0,0").is_err());
+
+        let input = "game 3 3 4\nteams 0 1 0 1 convert\n0 0,0";
+        let record = GameRecord::parse(input).unwrap();
+        assert_eq!(record.teams.assignment, vec![0, 1, 0, 1]);
+        assert!(record.teams.convert_allies);
+        assert_eq!(record.to_string(), input);
+    }
+}
@@ -0,0 +1,212 @@
+//! Ready-made observers for `Runner::run_observed`.
+
+use crate::chain_reaction::{ChainReaction, Outcome};
+use crate::notation::GameRecord;
+use crate::runner::{RunEvent, RunObserver};
+use std::io::{self, Write};
+
+/// Writes a line for every event, worded like the `replay` command.
+pub struct LogObserver<W: Write> {
+    out: W,
+    /// First error the writer ran into. Nothing is written after it.
+    error: Option<io::Error>,
+}
+
+impl<W: Write> LogObserver<W> {
+    pub fn new(out: W) -> LogObserver<W> {
+        LogObserver { out, error: None }
+    }
+
+    /// Returns the writer, or the first error it ran into.
+    pub fn into_inner(self) -> io::Result<W> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(self.out),
+        }
+    }
+
+    fn log(&mut self, event: &RunEvent) -> io::Result<()> {
+        match *event {
+            RunEvent::GameStarted { game } => writeln!(self.out, "Game {} started.", game),
+            RunEvent::MoveMade { player, pos } => {
+                writeln!(self.out, "Player {} plays {} {}.", player, pos.0, pos.1)
+            }
+            RunEvent::Exploded(explosion) if explosion.waves.is_empty() => {
+                writeln!(self.out, "The move took over the cell.")
+            }
+            RunEvent::Exploded(explosion) => writeln!(
+                self.out,
+                "The explosion took {} waves and captured {} cells.",
+                explosion.waves.len(),
+                explosion.captured.len()
+            ),
+            RunEvent::PlayerEliminated(elimination) => match elimination.by {
+                Some(by) => writeln!(
+                    self.out,
+                    "Player {} was eliminated by player {} on move {}.",
+                    elimination.player, by, elimination.ply
+                ),
+                None => writeln!(self.out, "Player {} was already out.", elimination.player),
+            },
+            RunEvent::GameFinished { game, outcome, .. } => match outcome {
+                Outcome::Win(winner) => {
+                    writeln!(self.out, "Game {}: player {} wins.", game, winner)
+                }
+                Outcome::Draw => writeln!(self.out, "Game {} is a draw.", game),
+                Outcome::Aborted => writeln!(self.out, "Game {} was aborted.", game),
+            },
+        }
+    }
+}
+
+impl<W: Write> RunObserver for LogObserver<W> {
+    fn observe(&mut self, event: &RunEvent, _game: &ChainReaction) {
+        if self.error.is_none() {
+            self.error = self.log(event).err();
+        }
+    }
+}
+
+/// Totals over every game of a run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StatsObserver {
+    pub games: u32,
+    pub moves: u64,
+    pub explosions: u64,
+    pub captures: u64,
+    pub eliminations: u64,
+    /// Most waves a single explosion took.
+    pub longest_explosion: usize,
+    /// Fewest and most moves a game took.
+    pub shortest_game: Option<u32>,
+    pub longest_game: u32,
+}
+
+impl StatsObserver {
+    pub fn new() -> StatsObserver {
+        StatsObserver::default()
+    }
+
+    pub fn average_moves(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.moves as f64 / self.games as f64
+    }
+}
+
+impl RunObserver for StatsObserver {
+    fn observe(&mut self, event: &RunEvent, game: &ChainReaction) {
+        match event {
+            RunEvent::GameStarted { .. } => {}
+            RunEvent::MoveMade { .. } => self.moves += 1,
+            RunEvent::Exploded(explosion) => {
+                if !explosion.waves.is_empty() {
+                    self.explosions += 1;
+                }
+                self.captures += explosion.captured.len() as u64;
+                self.longest_explosion = self.longest_explosion.max(explosion.waves.len());
+            }
+            RunEvent::PlayerEliminated(_) => self.eliminations += 1,
+            RunEvent::GameFinished { .. } => {
+                let moves = game.move_count();
+                self.games += 1;
+                self.shortest_game = Some(self.shortest_game.map_or(moves, |min| min.min(moves)));
+                self.longest_game = self.longest_game.max(moves);
+            }
+        }
+    }
+}
+
+/// Keeps a record of every finished game, so that it can be saved or
+/// replayed later.
+#[derive(Clone, Debug, Default)]
+pub struct RecordingObserver {
+    pub records: Vec<GameRecord>,
+}
+
+impl RecordingObserver {
+    pub fn new() -> RecordingObserver {
+        RecordingObserver::default()
+    }
+}
+
+impl RunObserver for RecordingObserver {
+    fn observe(&mut self, event: &RunEvent, game: &ChainReaction) {
+        if let RunEvent::GameFinished { .. } = event {
+            self.records.push(GameRecord::from_game(game));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{players::from_name, Runner};
+
+    #[test]
+    fn observers_agree_with_the_report() {
+        let players = vec![
+            from_name("form_chains").unwrap(),
+            from_name("play_randomly").unwrap(),
+        ];
+        let mut runner = Runner::new(4, 4, players).unwrap();
+        runner.seed(3);
+
+        let mut stats = StatsObserver::new();
+        let mut recording = RecordingObserver::new();
+        let mut log = LogObserver::new(Vec::new());
+        let report = runner
+            .run_observed(10, &mut (&mut stats, (&mut recording, &mut log)))
+            .unwrap();
+
+        assert_eq!(stats.games, 10);
+        assert_eq!(recording.records.len(), 10);
+        let moves: usize = recording.records.iter().map(|r| r.moves.len()).sum();
+        assert_eq!(stats.moves, moves as u64);
+        assert_eq!(stats.eliminations, 10);
+        assert!(stats.shortest_game.unwrap() <= stats.longest_game);
+
+        for record in &recording.records {
+            assert_eq!(record.replay().unwrap().outcome(), record.outcome);
+        }
+        let wins = recording
+            .records
+            .iter()
+            .filter(|r| r.outcome == Some(Outcome::Win(0)));
+        assert_eq!(wins.count(), report.wins[0]);
+
+        let log = String::from_utf8(log.into_inner().unwrap()).unwrap();
+        assert!(log.starts_with("Game 1 started.\nPlayer 0 plays "));
+        assert_eq!(log.matches(" wins.").count(), 10);
+    }
+
+    /// Stops the run once a number of moves have been made.
+    struct StopAfter(u64, StatsObserver);
+
+    impl RunObserver for StopAfter {
+        fn observe(&mut self, event: &RunEvent, game: &ChainReaction) {
+            self.1.observe(event, game);
+        }
+
+        fn should_stop(&mut self) -> bool {
+            self.1.moves >= self.0
+        }
+    }
+
+    #[test]
+    fn stops_between_moves() {
+        let players = vec![
+            from_name("play_randomly").unwrap(),
+            from_name("play_randomly").unwrap(),
+        ];
+        let mut runner = Runner::new(5, 5, players).unwrap();
+        runner.seed(1);
+
+        let mut observer = StopAfter(3, StatsObserver::new());
+        let report = runner.run_observed(10, &mut observer).unwrap();
+        assert_eq!(report.games(), 0);
+        assert_eq!(observer.1.moves, 3);
+        assert_eq!(runner.game().move_count(), 3);
+    }
+}
@@ -5,3 +5,53 @@ mod play_randomly;
 pub use avoid_others::AvoidOthers;
 pub use form_chains::FormChains;
 pub use play_randomly::PlayRandomly;
+
+use crate::{spec::PlayerSpec, Player};
+
+/// Builds a built-in strategy with its default parameters.
+pub fn from_name(name: &str) -> Result<Box<dyn Player>, &'static str> {
+    PlayerSpec {
+        name: name.to_string(),
+        params: Vec::new(),
+    }
+    .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_position, ChainReaction, GameContext, STRATEGY_INFO};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn strategies_report_when_nothing_is_available() {
+        // Player 2 is to move on a full board without any cells of its own.
+        let game = parse_position("position 2 2 3 2 4\n1:0 1:1\n1:1 1:0").unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+
+        for info in STRATEGY_INFO.iter() {
+            let mut player = from_name(info.name).unwrap();
+            assert_eq!(
+                player.play(GameContext::new(&game, &mut rng)),
+                Err("There are no available cells."),
+                "{}",
+                info.name
+            );
+        }
+    }
+
+    #[test]
+    fn avoid_others_breaks_ties_at_random() {
+        // Every cell of an empty board is equally quiet.
+        let game = ChainReaction::new(3, 3, 2).unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut player = AvoidOthers::new();
+
+        let chosen: BTreeSet<_> = (0..20)
+            .map(|_| player.play(GameContext::new(&game, &mut rng)).unwrap())
+            .collect();
+        assert!(chosen.len() > 1);
+    }
+}
@@ -1,26 +1,46 @@
 use crate::{runner::GameContext, Player, Pos};
+use rand::Rng;
 
 #[derive(Clone)]
-pub struct AvoidOthers {}
+pub struct AvoidOthers {
+    /// How many steps from a cell orbs are counted, 1 being just its
+    /// neighbours.
+    pub radius: usize,
+}
 
-impl Player for AvoidOthers {
-    fn play(&mut self, ctx: GameContext) -> Result<Pos, &'static str> {
-        let choice = ctx.available_cells();
-        let mut choice: Vec<_> = choice
-            .iter()
-            .map(|pos| {
-                let neighbor_mass_sum = ctx
-                    .neighbors(pos)
-                    .iter()
-                    .map(|(n, _)| ctx.mass(&n))
-                    .fold(0, |acc, x| acc + x);
+impl AvoidOthers {
+    pub fn new() -> Self {
+        AvoidOthers { radius: 1 }
+    }
+}
 
-                (pos, neighbor_mass_sum)
-            })
-            .collect();
+impl Default for AvoidOthers {
+    fn default() -> Self {
+        AvoidOthers::new()
+    }
+}
 
-        choice.sort_by(|a, b| a.1.cmp(&b.1));
+impl Player for AvoidOthers {
+    /// Plays a cell with the fewest orbs around it, picking at random among
+    /// equally quiet cells.
+    fn play(&mut self, mut ctx: GameContext) -> Result<Pos, &'static str> {
+        let mut quietest = Vec::new();
+        let mut lowest = u32::MAX;
+        for pos in ctx.legal_moves() {
+            let mass = ctx.mass_within(&pos, self.radius)?;
+            if mass < lowest {
+                lowest = mass;
+                quietest.clear();
+            }
+            if mass == lowest {
+                quietest.push(pos);
+            }
+        }
 
-        return Ok(*choice[0].0);
+        if quietest.is_empty() {
+            return Err("There are no available cells.");
+        }
+        let index = ctx.rng().gen_range(0..quietest.len());
+        Ok(quietest[index])
     }
 }
@@ -1,7 +1,7 @@
 use crate::{runner::GameContext, Player, Pos};
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct FormChains {
     current: Option<Pos>,
 }
@@ -13,13 +13,18 @@ impl FormChains {
 }
 
 impl Player for FormChains {
-    fn play(&mut self, ctx: GameContext) -> Result<Pos, &'static str> {
+    fn play(&mut self, mut ctx: GameContext) -> Result<Pos, &'static str> {
         let available = ctx.available_cells();
+        if available.is_empty() {
+            return Err("There are no available cells.");
+        }
 
-        let non_critical: Vec<_> = available
-            .iter()
-            .filter(|p| ctx.mass(p) < ctx.capacity(p) - 1)
-            .collect();
+        let mut non_critical = Vec::new();
+        for p in &available {
+            if ctx.mass(p)? + 1 < ctx.capacity(p)? {
+                non_critical.push(p);
+            }
+        }
 
         let choice: Vec<_> = match non_critical.len() {
             0 => available.iter().collect(),
@@ -28,26 +33,27 @@ impl Player for FormChains {
 
         if self
             .current
-            .map_or(true, |current| !available.contains(&current))
+            .is_none_or(|current| !available.contains(&current))
         {
-            let index = thread_rng().gen_range(0..choice.len());
+            let index = ctx.rng().gen_range(0..choice.len());
             self.current = Some(*choice[index]);
         } else if let Some(pos) = self.current {
-            if ctx.mass(&pos) == ctx.capacity(&pos) - 1 {
-                let neighbors = ctx.neighbors(&pos);
-                let neighbors: Vec<_> = neighbors
-                    .iter()
-                    .filter(|(n, _)| available.contains(n) && ctx.mass(n) < ctx.capacity(n) - 1)
-                    .collect();
+            if ctx.mass(&pos)? + 1 == ctx.capacity(&pos)? {
+                let mut neighbors = Vec::new();
+                for (n, _) in ctx.neighbors(&pos) {
+                    if available.contains(&n) && ctx.mass(&n)? + 1 < ctx.capacity(&n)? {
+                        neighbors.push(n);
+                    }
+                }
 
                 self.current = match neighbors.len() {
                     0 => {
-                        let index = thread_rng().gen_range(0..choice.len());
+                        let index = ctx.rng().gen_range(0..choice.len());
                         Some(*choice[index])
                     }
                     _ => {
-                        let index = thread_rng().gen_range(0..neighbors.len());
-                        Some(neighbors[index].0)
+                        let index = ctx.rng().gen_range(0..neighbors.len());
+                        Some(neighbors[index])
                     }
                 };
             }
@@ -55,4 +61,25 @@ impl Player for FormChains {
 
         Ok(self.current.expect("No current cell selected."))
     }
+
+    /// The cell currently being filled as `x y`, or `-` if there is none.
+    fn save_state(&self) -> String {
+        match self.current {
+            Some(Pos(x, y)) => format!("{} {}", x, y),
+            None => "-".to_string(),
+        }
+    }
+
+    fn load_state(&mut self, state: &str) -> Result<(), &'static str> {
+        self.current = match state.split_whitespace().collect::<Vec<_>>()[..] {
+            ["-"] => None,
+            [x, y] => {
+                let x = x.parse().map_err(|_| "Invalid FormChains state.")?;
+                let y = y.parse().map_err(|_| "Invalid FormChains state.")?;
+                Some(Pos(x, y))
+            }
+            _ => return Err("Invalid FormChains state."),
+        };
+        Ok(())
+    }
 }
@@ -1,19 +1,20 @@
 use crate::{runner::GameContext, Player, Pos};
-use rand::{thread_rng, Rng};
+use rand::Rng;
 
 #[derive(Clone)]
 pub struct PlayRandomly {}
 
 impl Player for PlayRandomly {
-    fn play(&mut self, ctx: GameContext) -> Result<Pos, &'static str> {
-        let available = ctx.available_cells();
-        let available: Vec<_> = available.iter().collect();
+    fn play(&mut self, mut ctx: GameContext) -> Result<Pos, &'static str> {
+        let count = ctx.legal_move_count();
 
-        if available.len() == 0 {
+        if count == 0 {
             Err("There are no available cells.")
         } else {
-            let index = thread_rng().gen_range(0..available.len());
-            Ok(*available[index])
+            let index = ctx.rng().gen_range(0..count);
+            ctx.legal_moves()
+                .nth(index)
+                .ok_or("There are no available cells.")
         }
     }
 }
@@ -0,0 +1,36 @@
+#[cfg(feature = "animation")]
+mod animation;
+mod heatmap;
+#[cfg(feature = "animation")]
+mod raster;
+mod svg;
+mod text;
+
+#[cfg(feature = "animation")]
+pub use animation::{render_animation, AnimationFormat, AnimationStyle};
+pub use heatmap::{heatmap_csv, render_heatmap_svg, render_heatmap_text};
+pub use svg::{render_svg, SvgStyle};
+pub use text::{render_text, TextStyle};
+
+/// RGB player colours for image output, matching the web app.
+const PLAYER_COLORS: [[u8; 3]; 8] = [
+    [255, 0, 0],     // red
+    [0, 0, 255],     // blue
+    [0, 128, 0],     // green
+    [128, 0, 128],   // purple
+    [210, 105, 30],  // chocolate
+    [0, 0, 139],     // darkblue
+    [105, 105, 105], // dimgrey
+    [147, 112, 219], // mediumpurple
+];
+
+pub fn player_color(player: usize) -> [u8; 3] {
+    PLAYER_COLORS[player % PLAYER_COLORS.len()]
+}
+
+/// ANSI foreground colour codes, one per player.
+const ANSI_COLORS: [u8; 6] = [31, 34, 32, 33, 35, 36];
+
+pub fn ansi_color(player: usize) -> u8 {
+    ANSI_COLORS[player % ANSI_COLORS.len()]
+}
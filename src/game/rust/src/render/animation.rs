@@ -0,0 +1,225 @@
+use super::raster::{board_size, draw_board, Canvas};
+use crate::array2d::Pos;
+use crate::chain_reaction::ChainReaction;
+use crate::notation::GameRecord;
+use std::convert::TryInto;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AnimationStyle {
+    /// Side of a single cell in pixels.
+    pub cell_size: u32,
+    /// How long each move stays on screen, in milliseconds.
+    pub frame_delay: u16,
+    /// Add a frame for every wave of an explosion.
+    pub wave_frames: bool,
+    /// How long each wave frame stays on screen, in milliseconds.
+    pub wave_delay: u16,
+}
+
+impl Default for AnimationStyle {
+    fn default() -> Self {
+        AnimationStyle {
+            cell_size: 32,
+            frame_delay: 500,
+            wave_frames: false,
+            wave_delay: 150,
+        }
+    }
+}
+
+/// A frame of the animation, drawn only once it is about to be encoded.
+struct Frame<'a> {
+    game: &'a ChainReaction,
+    last_move: Option<Pos>,
+    exploding: &'a [Pos],
+    delay: u16,
+}
+
+impl Frame<'_> {
+    fn draw(&self, cell_size: u32) -> Result<Canvas, &'static str> {
+        draw_board(self.game, cell_size, self.last_move, self.exploding)
+    }
+}
+
+/// Replays a game and hands every frame to `emit` in order, so that only one
+/// of them is ever drawn at a time.
+fn frames(
+    record: &GameRecord,
+    style: &AnimationStyle,
+    emit: &mut dyn FnMut(Frame) -> Result<(), &'static str>,
+) -> Result<(), &'static str> {
+    let game = record.new_game()?;
+    emit(Frame {
+        game: &game,
+        last_move: None,
+        exploding: &[],
+        delay: style.frame_delay,
+    })?;
+
+    for m in &record.moves {
+        game.skip_to(m.player)?;
+
+        let mut emitted = Ok(());
+        let mut on_wave = |game: &ChainReaction, wave: &[Pos]| {
+            if style.wave_frames && emitted.is_ok() {
+                emitted = emit(Frame {
+                    game,
+                    last_move: None,
+                    exploding: wave,
+                    delay: style.wave_delay,
+                });
+            }
+        };
+        game.place_stepwise(&m.pos, &mut on_wave)?;
+        emitted?;
+
+        emit(Frame {
+            game: &game,
+            last_move: Some(m.pos),
+            exploding: &[],
+            delay: style.frame_delay,
+        })?;
+    }
+
+    Ok(())
+}
+
+fn frame_count(record: &GameRecord, style: &AnimationStyle) -> Result<u32, &'static str> {
+    let mut count = 0;
+    frames(record, style, &mut |_| {
+        count += 1;
+        Ok(())
+    })?;
+    Ok(count)
+}
+
+fn encode_gif(record: &GameRecord, style: &AnimationStyle) -> Result<Vec<u8>, &'static str> {
+    const ERROR: &str = "Failed to encode GIF.";
+
+    let (width, height) = board_size(&record.new_game()?, style.cell_size)?;
+    let width: u16 = width.try_into().map_err(|_| "Image is too large.")?;
+    let height: u16 = height.try_into().map_err(|_| "Image is too large.")?;
+
+    let mut out = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut out, width, height, &[]).map_err(|_| ERROR)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(|_| ERROR)?;
+
+        frames(record, style, &mut |frame| {
+            let mut canvas = frame.draw(style.cell_size)?;
+            let mut gif_frame = gif::Frame::from_rgba_speed(width, height, &mut canvas.pixels, 10);
+            // GIF delays are measured in hundredths of a second, and viewers
+            // treat a delay of 0 as no delay given.
+            gif_frame.delay = (frame.delay / 10).max(1);
+            encoder.write_frame(&gif_frame).map_err(|_| ERROR)
+        })?;
+    }
+
+    Ok(out)
+}
+
+fn encode_apng(record: &GameRecord, style: &AnimationStyle) -> Result<Vec<u8>, &'static str> {
+    const ERROR: &str = "Failed to encode APNG.";
+
+    let (width, height) = board_size(&record.new_game()?, style.cell_size)?;
+    // The frame count goes in the header, so the game is replayed once
+    // without drawing to find it.
+    let count = frame_count(record, style)?;
+
+    let mut out = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut out, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_animated(count, 0).map_err(|_| ERROR)?;
+
+        let mut writer = encoder.write_header().map_err(|_| ERROR)?;
+        frames(record, style, &mut |frame| {
+            let canvas = frame.draw(style.cell_size)?;
+            writer
+                .set_frame_delay(frame.delay, 1000)
+                .map_err(|_| ERROR)?;
+            writer.write_image_data(&canvas.pixels).map_err(|_| ERROR)
+        })?;
+        writer.finish().map_err(|_| ERROR)?;
+    }
+
+    Ok(out)
+}
+
+/// Replays a game and encodes every move, and optionally every explosion
+/// wave, as a frame of an animated image.
+pub fn render_animation(
+    record: &GameRecord,
+    style: &AnimationStyle,
+    format: AnimationFormat,
+) -> Result<Vec<u8>, &'static str> {
+    if style.cell_size == 0 {
+        return Err("Cell size cannot be zero.");
+    }
+
+    match format {
+        AnimationFormat::Gif => encode_gif(record, style),
+        AnimationFormat::Apng => encode_apng(record, style),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_frame_per_move_and_wave() {
+        let record = GameRecord::parse("game 2 2 2\n0 0,0\n1 1,1\n0 0,0").unwrap();
+
+        let style = AnimationStyle::default();
+        assert_eq!(frame_count(&record, &style), Ok(4));
+
+        // The last move explodes the corner in a single wave.
+        let style = AnimationStyle {
+            wave_frames: true,
+            ..style
+        };
+        assert_eq!(frame_count(&record, &style), Ok(4 + 1));
+
+        let gif = render_animation(&record, &style, AnimationFormat::Gif).unwrap();
+        assert_eq!(&gif[..6], b"GIF89a");
+        let apng = render_animation(&record, &style, AnimationFormat::Apng).unwrap();
+        assert_eq!(&apng[1..4], b"PNG");
+
+        // Delays too short for a GIF are rounded up rather than dropped.
+        let style = AnimationStyle {
+            frame_delay: 5,
+            wave_delay: 5,
+            ..style
+        };
+        let gif = render_animation(&record, &style, AnimationFormat::Gif).unwrap();
+        let mut decoder = gif::DecodeOptions::new().read_info(&gif[..]).unwrap();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!(frame.delay, 1);
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_images() {
+        let record = GameRecord::parse("game 2 2 2\n0 0,0").unwrap();
+        for &cell_size in &[10_000, u32::MAX] {
+            let style = AnimationStyle {
+                cell_size,
+                ..AnimationStyle::default()
+            };
+            assert_eq!(
+                render_animation(&record, &style, AnimationFormat::Gif),
+                Err("Image is too large.")
+            );
+        }
+    }
+}
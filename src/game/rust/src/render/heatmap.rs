@@ -0,0 +1,214 @@
+use super::text::{borders, rule, TextStyle};
+use crate::array2d::{Array2D, Pos};
+use std::fmt::{self, Write};
+
+/// Width of a cell's contents, enough for a value with two decimals.
+const CELL_WIDTH: usize = 6;
+
+/// ANSI 256-colour backgrounds from cold to hot.
+const ANSI_RAMP: [u8; 8] = [231, 230, 229, 223, 216, 209, 203, 196];
+
+fn values(map: &Array2D<f64>) -> impl Iterator<Item = f64> + '_ {
+    (0..map.height())
+        .flat_map(move |y| (0..map.width()).map(move |x| Pos(x, y)))
+        .map(move |pos| *map.get(&pos).unwrap())
+}
+
+/// Scales values into `0.0..=1.0` relative to the largest one, so that maps
+/// of rates and maps of counts are shaded alike.
+fn scale(map: &Array2D<f64>) -> impl Fn(f64) -> f64 {
+    let max = values(map)
+        .filter(|value| value.is_finite())
+        .fold(0.0, f64::max);
+    move |value| if max > 0.0 { value / max } else { 0.0 }
+}
+
+fn cell(value: f64, heat: f64, style: &TextStyle) -> String {
+    if value.is_nan() {
+        return format!("{:^width$}", "-", width = CELL_WIDTH);
+    }
+
+    let text = format!("{:^width$.2}", value, width = CELL_WIDTH);
+    if style.color {
+        let index = (heat * (ANSI_RAMP.len() - 1) as f64).round() as usize;
+        format!("\x1b[30;48;5;{}m{}\x1b[0m", ANSI_RAMP[index], text)
+    } else {
+        text
+    }
+}
+
+/// Draws a grid of values, shading each cell by its value when colour is
+/// enabled. NaN values are shown as `-`.
+pub fn render_heatmap_text(map: &Array2D<f64>, style: &TextStyle) -> String {
+    let borders = borders(style);
+    let heat = scale(map);
+    let width = map.width();
+    let margin = if style.coordinates {
+        " ".repeat(map.height().saturating_sub(1).to_string().len() + 1)
+    } else {
+        String::new()
+    };
+
+    let mut out = String::new();
+
+    if style.coordinates {
+        out += &margin;
+        out.push(' ');
+        for x in 0..width {
+            out += &format!("{:^width$} ", x, width = CELL_WIDTH);
+        }
+        out = out.trim_end().to_string();
+        out.push('\n');
+    }
+
+    out += &margin;
+    out += &rule(width, CELL_WIDTH, borders, borders.top);
+    out.push('\n');
+
+    for y in 0..map.height() {
+        if y != 0 {
+            out += &margin;
+            out += &rule(width, CELL_WIDTH, borders, borders.middle);
+            out.push('\n');
+        }
+
+        if style.coordinates {
+            out += &format!("{:>width$} ", y, width = margin.len() - 1);
+        }
+        out.push(borders.vertical);
+        for x in 0..width {
+            let value = *map.get(&Pos(x, y)).unwrap();
+            out += &cell(value, heat(value), style);
+            out.push(borders.vertical);
+        }
+        out.push('\n');
+    }
+
+    out += &margin;
+    out += &rule(width, CELL_WIDTH, borders, borders.bottom);
+    out.push('\n');
+
+    out
+}
+
+pub fn render_heatmap_svg(map: &Array2D<f64>, cell_size: u32) -> String {
+    let mut out = String::new();
+    write_svg(&mut out, map, cell_size).expect("Writing to a String cannot fail.");
+    out
+}
+
+fn write_svg(out: &mut String, map: &Array2D<f64>, cell_size: u32) -> fmt::Result {
+    let size = f64::from(cell_size);
+    let width = map.width() as f64 * size;
+    let height = map.height() as f64 * size;
+    let heat = scale(map);
+
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    )?;
+
+    for y in 0..map.height() {
+        for x in 0..map.width() {
+            let value = *map.get(&Pos(x, y)).unwrap();
+            let (left, top) = (x as f64 * size, y as f64 * size);
+
+            if value.is_nan() {
+                writeln!(
+                    out,
+                    r#"<rect x="{}" y="{}" width="{s}" height="{s}" fill="rgb(238,238,238)"/>"#,
+                    left,
+                    top,
+                    s = size
+                )?;
+                continue;
+            }
+
+            // Fade from white to red as the value approaches the maximum.
+            let shade = (255.0 * (1.0 - heat(value))).round();
+            writeln!(
+                out,
+                r#"<rect x="{}" y="{}" width="{s}" height="{s}" fill="rgb(255,{c},{c})"/>"#,
+                left,
+                top,
+                s = size,
+                c = shade
+            )?;
+            writeln!(
+                out,
+                r#"<text x="{}" y="{}" font-family="sans-serif" font-size="{}" text-anchor="middle" dominant-baseline="central">{:.2}</text>"#,
+                left + size / 2.0,
+                top + size / 2.0,
+                size / 4.0,
+                value
+            )?;
+        }
+    }
+
+    writeln!(out, r#"<g stroke="black" stroke-width="1">"#)?;
+    for x in 0..=map.width() {
+        let x = x as f64 * size;
+        writeln!(
+            out,
+            r#"<line x1="{x}" y1="0" x2="{x}" y2="{}"/>"#,
+            height,
+            x = x
+        )?;
+    }
+    for y in 0..=map.height() {
+        let y = y as f64 * size;
+        writeln!(
+            out,
+            r#"<line x1="0" y1="{y}" x2="{}" y2="{y}"/>"#,
+            width,
+            y = y
+        )?;
+    }
+    writeln!(out, "</g>")?;
+
+    writeln!(out, "</svg>")
+}
+
+/// One CSV row per board row. NaN values are left empty.
+pub fn heatmap_csv(map: &Array2D<f64>) -> String {
+    let mut out = String::new();
+    for y in 0..map.height() {
+        let row: Vec<String> = (0..map.width())
+            .map(|x| match *map.get(&Pos(x, y)).unwrap() {
+                value if value.is_nan() => String::new(),
+                value => value.to_string(),
+            })
+            .collect();
+        out += &row.join(",");
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_heatmap() {
+        let mut map = Array2D::new(0.0, Pos(2, 2));
+        *map.get_mut(&Pos(0, 0)).unwrap() = 0.5;
+        *map.get_mut(&Pos(1, 1)).unwrap() = f64::NAN;
+
+        let expected = "
++------+------+
+| 0.50 | 0.00 |
++------+------+
+| 0.00 |  -   |
++------+------+
+";
+        assert_eq!(
+            render_heatmap_text(&map, &TextStyle::plain()),
+            &expected[1..]
+        );
+        assert_eq!(heatmap_csv(&map), "0.5,0\n0,\n");
+        assert_eq!(render_heatmap_svg(&map, 10).matches("<text").count(), 3);
+    }
+}
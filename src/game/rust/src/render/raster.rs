@@ -0,0 +1,139 @@
+use super::player_color;
+use crate::array2d::Pos;
+use crate::chain_reaction::{ChainReaction, Field, FieldData};
+use std::convert::TryFrom;
+
+const WHITE: [u8; 3] = [255, 255, 255];
+const BLACK: [u8; 3] = [0, 0, 0];
+const LIME: [u8; 3] = [0, 255, 0];
+const EXPLODING: [u8; 3] = [255, 220, 120];
+
+/// Largest image, in pixels, that is drawn. Animations are encoded one frame
+/// at a time, so this also bounds what they hold besides the encoded image.
+const MAX_PIXELS: usize = 1 << 24;
+
+/// An RGBA image drawn without anti-aliasing, so that it only ever contains
+/// the handful of colours used by the board.
+pub struct Canvas {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, color: [u8; 3]) -> Result<Canvas, &'static str> {
+        let area = (width as usize)
+            .checked_mul(height as usize)
+            .filter(|&area| area <= MAX_PIXELS)
+            .ok_or("Image is too large.")?;
+
+        let pixel = [color[0], color[1], color[2], 255];
+        Ok(Canvas {
+            width,
+            height,
+            pixels: pixel.repeat(area),
+        })
+    }
+
+    fn set(&mut self, x: i64, y: i64, [r, g, b]: [u8; 3]) {
+        if x < 0 || y < 0 || x >= i64::from(self.width) || y >= i64::from(self.height) {
+            return;
+        }
+        let index = (y as usize * self.width as usize + x as usize) * 4;
+        self.pixels[index..index + 4].copy_from_slice(&[r, g, b, 255]);
+    }
+
+    pub fn fill_rect(&mut self, x: i64, y: i64, width: i64, height: i64, color: [u8; 3]) {
+        for py in y..y + height {
+            for px in x..x + width {
+                self.set(px, py, color);
+            }
+        }
+    }
+
+    pub fn fill_circle(&mut self, cx: f64, cy: f64, radius: f64, color: [u8; 3]) {
+        let (x0, x1) = ((cx - radius).floor() as i64, (cx + radius).ceil() as i64);
+        let (y0, y1) = ((cy - radius).floor() as i64, (cy + radius).ceil() as i64);
+
+        for py in y0..=y1 {
+            for px in x0..=x1 {
+                let (dx, dy) = (px as f64 + 0.5 - cx, py as f64 + 0.5 - cy);
+                if dx * dx + dy * dy <= radius * radius {
+                    self.set(px, py, color);
+                }
+            }
+        }
+    }
+}
+
+/// Offsets of orbs within a cell, as fractions of the cell size.
+fn orb_offsets(count: u32) -> &'static [(f64, f64)] {
+    match count {
+        0 => &[],
+        1 => &[(0.0, 0.0)],
+        2 => &[(-0.17, 0.0), (0.17, 0.0)],
+        3 => &[(0.0, -0.15), (-0.17, 0.13), (0.17, 0.13)],
+        _ => &[(-0.17, -0.17), (0.17, -0.17), (-0.17, 0.17), (0.17, 0.17)],
+    }
+}
+
+/// Width and height in pixels of the board as `draw_board` draws it.
+pub fn board_size(game: &ChainReaction, cell_size: u32) -> Result<(u32, u32), &'static str> {
+    // One pixel per cell for the grid lines, plus one to close it off.
+    let side = |cells: usize| {
+        u32::try_from(cells)
+            .ok()
+            .and_then(|cells| cells.checked_mul(cell_size))
+            .and_then(|pixels| pixels.checked_add(1))
+            .ok_or("Image is too large.")
+    };
+    Ok((side(game.width())?, side(game.height())?))
+}
+
+/// Draws the board with one small disc per orb. `last_move` gets a lime
+/// background like in the web app, and cells in `exploding` are tinted.
+pub fn draw_board(
+    game: &ChainReaction,
+    cell_size: u32,
+    last_move: Option<Pos>,
+    exploding: &[Pos],
+) -> Result<Canvas, &'static str> {
+    let (width, height) = board_size(game, cell_size)?;
+    let size = i64::from(cell_size);
+    let mut canvas = Canvas::new(width, height, WHITE)?;
+
+    for &Pos(x, y) in exploding {
+        canvas.fill_rect(x as i64 * size, y as i64 * size, size, size, EXPLODING);
+    }
+    if let Some(Pos(x, y)) = last_move {
+        canvas.fill_rect(x as i64 * size, y as i64 * size, size, size, LIME);
+    }
+
+    for x in 0..=game.width() as i64 {
+        canvas.fill_rect(x * size, 0, 1, i64::from(canvas.height), BLACK);
+    }
+    for y in 0..=game.height() as i64 {
+        canvas.fill_rect(0, y * size, i64::from(canvas.width), 1, BLACK);
+    }
+
+    for y in 0..game.height() {
+        for x in 0..game.width() {
+            if let Ok(Field::Owned(FieldData { owner, count })) =
+                game.grid.get(&Pos(x, y)).map(|c| c.get())
+            {
+                let size = f64::from(cell_size);
+                let (cx, cy) = ((x as f64 + 0.5) * size, (y as f64 + 0.5) * size);
+                for &(dx, dy) in orb_offsets(count) {
+                    canvas.fill_circle(
+                        cx + dx * size,
+                        cy + dy * size,
+                        size * 0.14,
+                        player_color(owner),
+                    );
+                }
+            }
+        }
+    }
+
+    Ok(canvas)
+}
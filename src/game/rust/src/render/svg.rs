@@ -0,0 +1,194 @@
+use super::player_color;
+use crate::array2d::Pos;
+use crate::chain_reaction::{ChainReaction, Field, FieldData};
+use std::fmt::{self, Write};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SvgStyle {
+    /// Side of a single cell in pixels.
+    pub cell_size: u32,
+    /// Label rows and columns with their indices.
+    pub coordinates: bool,
+    /// Cell to highlight as the most recent move.
+    pub last_move: Option<Pos>,
+    /// Cells exploding in the wave to draw. Each gets an arrow pointing at
+    /// every neighbour it sends an orb to.
+    pub wave: Vec<Pos>,
+}
+
+impl Default for SvgStyle {
+    fn default() -> Self {
+        SvgStyle {
+            cell_size: 48,
+            coordinates: false,
+            last_move: None,
+            wave: Vec::new(),
+        }
+    }
+}
+
+pub fn render_svg(game: &ChainReaction, style: &SvgStyle) -> String {
+    let mut out = String::new();
+    write_svg(&mut out, game, style).expect("Writing to a String cannot fail.");
+    out
+}
+
+fn write_svg(out: &mut String, game: &ChainReaction, style: &SvgStyle) -> fmt::Result {
+    let size = f64::from(style.cell_size);
+    let margin = if style.coordinates { size / 2.0 } else { 0.0 };
+    let width = game.width() as f64 * size + margin;
+    let height = game.height() as f64 * size + margin;
+    let center = |Pos(x, y): Pos| {
+        (
+            margin + (x as f64 + 0.5) * size,
+            margin + (y as f64 + 0.5) * size,
+        )
+    };
+
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    )?;
+    writeln!(
+        out,
+        r#"<defs><marker id="arrow" viewBox="0 0 10 10" refX="8" refY="5" markerWidth="5" markerHeight="5" orient="auto"><path d="M 0 0 L 10 5 L 0 10 z" fill="black"/></marker></defs>"#
+    )?;
+    writeln!(
+        out,
+        r#"<rect width="{}" height="{}" fill="white"/>"#,
+        width, height
+    )?;
+
+    if let Some(Pos(x, y)) = style.last_move {
+        writeln!(
+            out,
+            r#"<rect x="{}" y="{}" width="{s}" height="{s}" fill="lime"/>"#,
+            margin + x as f64 * size,
+            margin + y as f64 * size,
+            s = size
+        )?;
+    }
+
+    writeln!(out, r#"<g stroke="black" stroke-width="1">"#)?;
+    for x in 0..=game.width() {
+        let x = margin + x as f64 * size;
+        writeln!(
+            out,
+            r#"<line x1="{x}" y1="{}" x2="{x}" y2="{}"/>"#,
+            margin,
+            height,
+            x = x
+        )?;
+    }
+    for y in 0..=game.height() {
+        let y = margin + y as f64 * size;
+        writeln!(
+            out,
+            r#"<line x1="{}" y1="{y}" x2="{}" y2="{y}"/>"#,
+            margin,
+            width,
+            y = y
+        )?;
+    }
+    writeln!(out, "</g>")?;
+
+    if style.coordinates {
+        writeln!(
+            out,
+            r#"<g font-family="sans-serif" font-size="{}" text-anchor="middle" dominant-baseline="central">"#,
+            size / 4.0
+        )?;
+        for x in 0..game.width() {
+            let (cx, _) = center(Pos(x, 0));
+            writeln!(out, r#"<text x="{}" y="{}">{}</text>"#, cx, margin / 2.0, x)?;
+        }
+        for y in 0..game.height() {
+            let (_, cy) = center(Pos(0, y));
+            writeln!(out, r#"<text x="{}" y="{}">{}</text>"#, margin / 2.0, cy, y)?;
+        }
+        writeln!(out, "</g>")?;
+    }
+
+    for y in 0..game.height() {
+        for x in 0..game.width() {
+            let pos = Pos(x, y);
+            let (owner, count) = match game.grid.get(&pos).map(|c| c.get()) {
+                Ok(Field::Owned(FieldData { owner, count })) => (owner, count),
+                _ => continue,
+            };
+
+            // Cells about to explode are drawn larger, like in the web app.
+            let critical = game.capacity().get(&pos).is_ok_and(|&c| count + 1 >= c);
+            let radius = size * if critical { 0.3125 } else { 0.25 };
+            let [r, g, b] = player_color(owner);
+            let (cx, cy) = center(pos);
+
+            writeln!(
+                out,
+                r#"<circle cx="{}" cy="{}" r="{}" fill="rgb({},{},{})"/>"#,
+                cx, cy, radius, r, g, b
+            )?;
+            writeln!(
+                out,
+                r#"<text x="{}" y="{}" font-family="sans-serif" font-size="{}" text-anchor="middle" dominant-baseline="central" fill="white">{}</text>"#,
+                cx,
+                cy,
+                size / 4.0,
+                count
+            )?;
+        }
+    }
+
+    if !style.wave.is_empty() {
+        writeln!(
+            out,
+            r#"<g stroke="black" stroke-width="{}" marker-end="url(#arrow)">"#,
+            size / 16.0
+        )?;
+        for &pos in &style.wave {
+            let (x1, y1) = center(pos);
+            for (neighbor, _) in game.neighbors(&pos) {
+                let (x2, y2) = center(neighbor);
+                let (dx, dy) = (x2 - x1, y2 - y1);
+                writeln!(
+                    out,
+                    r#"<line x1="{}" y1="{}" x2="{}" y2="{}"/>"#,
+                    x1 + dx * 0.3,
+                    y1 + dy * 0.3,
+                    x1 + dx * 0.75,
+                    y1 + dy * 0.75
+                )?;
+            }
+        }
+        writeln!(out, "</g>")?;
+    }
+
+    writeln!(out, "</svg>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_cells_and_arrows() {
+        let game = ChainReaction::new(3, 3, 2).unwrap();
+        game.place(&Pos(1, 1)).unwrap();
+        game.place(&Pos(2, 2)).unwrap();
+        game.place(&Pos(1, 1)).unwrap();
+
+        let style = SvgStyle {
+            last_move: Some(Pos(1, 1)),
+            wave: vec![Pos(1, 1)],
+            ..SvgStyle::default()
+        };
+        let svg = render_svg(&game, &style);
+
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<circle").count(), 2);
+        assert_eq!(svg.matches("fill=\"lime\"").count(), 1);
+        assert_eq!(svg.matches("<line").count(), 8 + 4);
+    }
+}
@@ -0,0 +1,217 @@
+use super::ansi_color;
+use crate::array2d::Pos;
+use crate::chain_reaction::{ChainReaction, Field, FieldData};
+
+/// Width of a cell's contents, enough for three orbs.
+const CELL_WIDTH: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TextStyle {
+    /// Colour each cell with its owner's ANSI colour. Without colour, owners
+    /// are told apart by letter instead.
+    pub color: bool,
+    /// Draw box characters and orb glyphs instead of plain ASCII.
+    pub unicode: bool,
+    /// Label rows and columns with their indices.
+    pub coordinates: bool,
+    /// Emphasize cells that will explode on the next placement.
+    pub highlight_critical: bool,
+}
+
+impl TextStyle {
+    pub fn fancy() -> TextStyle {
+        TextStyle {
+            color: true,
+            unicode: true,
+            coordinates: false,
+            highlight_critical: true,
+        }
+    }
+
+    pub fn plain() -> TextStyle {
+        TextStyle {
+            color: false,
+            unicode: false,
+            coordinates: false,
+            highlight_critical: true,
+        }
+    }
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        TextStyle::fancy()
+    }
+}
+
+pub(super) struct Borders {
+    pub(super) horizontal: char,
+    pub(super) vertical: char,
+    pub(super) top: [char; 3],
+    pub(super) middle: [char; 3],
+    pub(super) bottom: [char; 3],
+}
+
+const UNICODE_BORDERS: Borders = Borders {
+    horizontal: '─',
+    vertical: '│',
+    top: ['┌', '┬', '┐'],
+    middle: ['├', '┼', '┤'],
+    bottom: ['└', '┴', '┘'],
+};
+
+const ASCII_BORDERS: Borders = Borders {
+    horizontal: '-',
+    vertical: '|',
+    top: ['+', '+', '+'],
+    middle: ['+', '+', '+'],
+    bottom: ['+', '+', '+'],
+};
+
+pub(super) fn borders(style: &TextStyle) -> &'static Borders {
+    if style.unicode {
+        &UNICODE_BORDERS
+    } else {
+        &ASCII_BORDERS
+    }
+}
+
+pub(super) fn rule(
+    width: usize,
+    cell_width: usize,
+    borders: &Borders,
+    [left, join, right]: [char; 3],
+) -> String {
+    let segment: String = std::iter::repeat_n(borders.horizontal, cell_width).collect();
+    let mut line = String::new();
+    line.push(left);
+    for x in 0..width {
+        if x != 0 {
+            line.push(join);
+        }
+        line += &segment;
+    }
+    line.push(right);
+    line
+}
+
+/// Draws the mass of a cell as orbs, or as a number followed by a single
+/// orb if there are too many to fit.
+fn orbs(glyph: char, count: u32) -> String {
+    let count = count as usize;
+    if count <= CELL_WIDTH {
+        std::iter::repeat_n(glyph, count).collect()
+    } else {
+        format!("{}{}", count, glyph)
+    }
+}
+
+fn cell(game: &ChainReaction, pos: &Pos, style: &TextStyle) -> String {
+    let data = match game.grid.get(pos).map(|c| c.get()) {
+        Ok(Field::Owned(data)) => data,
+        _ => {
+            let empty = if style.unicode { '·' } else { '.' };
+            return format!("{:^width$}", empty, width = CELL_WIDTH);
+        }
+    };
+
+    let FieldData { owner, count } = data;
+    let critical =
+        style.highlight_critical && game.capacity().get(pos).is_ok_and(|&c| count + 1 >= c);
+
+    if style.color {
+        let glyph = if style.unicode { '●' } else { 'o' };
+        let emphasis = if critical { "1;4;" } else { "" };
+        format!(
+            "\x1b[{}{}m{:^width$}\x1b[0m",
+            emphasis,
+            ansi_color(owner),
+            orbs(glyph, count),
+            width = CELL_WIDTH
+        )
+    } else {
+        let letter = (b'a' + (owner % 26) as u8) as char;
+        let letter = if critical {
+            letter.to_ascii_uppercase()
+        } else {
+            letter
+        };
+        format!("{:^width$}", orbs(letter, count), width = CELL_WIDTH)
+    }
+}
+
+pub fn render_text(game: &ChainReaction, style: &TextStyle) -> String {
+    let borders = borders(style);
+    let width = game.width();
+    let margin = if style.coordinates {
+        " ".repeat(game.height().saturating_sub(1).to_string().len() + 1)
+    } else {
+        String::new()
+    };
+
+    let mut out = String::new();
+
+    if style.coordinates {
+        out += &margin;
+        out.push(' ');
+        for x in 0..width {
+            out += &format!("{:^width$} ", x, width = CELL_WIDTH);
+        }
+        out = out.trim_end().to_string();
+        out.push('\n');
+    }
+
+    out += &margin;
+    out += &rule(width, CELL_WIDTH, borders, borders.top);
+    out.push('\n');
+
+    for y in 0..game.height() {
+        if y != 0 {
+            out += &margin;
+            out += &rule(width, CELL_WIDTH, borders, borders.middle);
+            out.push('\n');
+        }
+
+        if style.coordinates {
+            out += &format!("{:>width$} ", y, width = margin.len() - 1);
+        }
+        out.push(borders.vertical);
+        for x in 0..width {
+            out += &cell(game, &Pos(x, y), style);
+            out.push(borders.vertical);
+        }
+        out.push('\n');
+    }
+
+    out += &margin;
+    out += &rule(width, CELL_WIDTH, borders, borders.bottom);
+    out.push('\n');
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_board() {
+        let game = ChainReaction::new(3, 2, 2).unwrap();
+        game.place(&Pos(0, 0)).unwrap();
+        game.place(&Pos(1, 0)).unwrap();
+
+        let style = TextStyle {
+            coordinates: true,
+            ..TextStyle::plain()
+        };
+        let expected = "
+    0   1   2
+  +---+---+---+
+0 | A | b | . |
+  +---+---+---+
+1 | . | . | . |
+  +---+---+---+
+";
+        assert_eq!(render_text(&game, &style), &expected[1..]);
+    }
+}
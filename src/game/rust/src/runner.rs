@@ -1,22 +1,50 @@
 use crate::array2d::{Array2D, Pos};
-use crate::chain_reaction::{ChainReaction, Field};
+use crate::bitboard::CellSet;
+use crate::chain_reaction::{
+    ChainReaction, Elimination, Explosion, Field, Limits, Move, Neighbors, Outcome, Rules, Teams,
+};
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use std::{
-    cell::{Cell, RefCell},
-    collections::HashSet,
-    convert::TryInto,
+    cell::{Cell, Ref, RefCell},
+    collections::BTreeSet,
 };
 
 pub trait Player {
     fn play(&mut self, ctx: GameContext) -> Result<Pos, &'static str>;
+
+    /// Whatever the player carries over from one move to the next, such as
+    /// a plan, as text that `load_state` accepts. Players without any state
+    /// return an empty string.
+    fn save_state(&self) -> String {
+        String::new()
+    }
+
+    /// Picks up the state returned by `save_state`, leaving the player as it
+    /// was if the state is rejected.
+    fn load_state(&mut self, state: &str) -> Result<(), &'static str> {
+        if state.is_empty() {
+            Ok(())
+        } else {
+            Err("Player does not keep any state.")
+        }
+    }
 }
 
 pub struct GameContext<'a> {
     game: &'a ChainReaction,
+    rng: &'a mut dyn RngCore,
 }
 
 impl<'a> GameContext<'a> {
-    pub fn new(game: &'a ChainReaction) -> Self {
-        GameContext { game }
+    pub fn new(game: &'a ChainReaction, rng: &'a mut dyn RngCore) -> Self {
+        GameContext { game, rng }
+    }
+
+    /// Source of randomness for players. Using it instead of a thread-local
+    /// generator keeps games reproducible when the runner is seeded.
+    pub fn rng(&mut self) -> &mut dyn RngCore {
+        self.rng
     }
 
     pub fn width(&self) -> usize {
@@ -27,6 +55,10 @@ impl<'a> GameContext<'a> {
         self.game.height()
     }
 
+    pub fn players(&self) -> usize {
+        self.game.players()
+    }
+
     pub fn player(&self) -> usize {
         self.game.current_player()
     }
@@ -35,141 +67,415 @@ impl<'a> GameContext<'a> {
         &self.game.grid
     }
 
-    pub fn empty_cells(&self) -> &RefCell<HashSet<Pos>> {
+    pub fn empty_cells(&self) -> &RefCell<CellSet> {
         &self.game.empty_cells
     }
 
-    pub fn owned_cells(&self) -> &RefCell<Vec<HashSet<Pos>>> {
+    pub fn owned_cells(&self) -> &RefCell<Vec<CellSet>> {
         &self.game.owned_cells
     }
 
-    pub fn available_cells(&self) -> HashSet<Pos> {
-        let player = self.game.current_player();
+    pub fn available_cells(&self) -> BTreeSet<Pos> {
+        self.game.legal_moves().collect()
+    }
+
+    /// Same cells as `available_cells`, in the same order, without
+    /// allocating.
+    pub fn legal_moves(&self) -> impl Iterator<Item = Pos> + '_ {
+        self.game.legal_moves()
+    }
+
+    pub fn legal_moves_into(&self, moves: &mut Vec<Pos>) {
+        self.game.legal_moves_into(moves)
+    }
+
+    pub fn legal_move_count(&self) -> usize {
+        self.game.legal_move_count()
+    }
+
+    pub fn capacity(&self, pos: &Pos) -> Result<u32, &'static str> {
+        self.game.capacity().get(pos).copied()
+    }
+
+    pub fn mass(&self, pos: &Pos) -> Result<u32, &'static str> {
+        match self.game.grid.get(pos)?.get() {
+            Field::Empty => Ok(0),
+            Field::Owned(data) => Ok(data.count),
+        }
+    }
+
+    pub fn neighbors(&self, pos: &Pos) -> Neighbors<'_> {
+        self.game.neighbors(pos)
+    }
+
+    /// Orbs on the cells within `radius` steps of `pos`, not counting `pos`
+    /// itself.
+    pub fn mass_within(&self, pos: &Pos, radius: usize) -> Result<u32, &'static str> {
+        self.game.mass_within(pos, radius)
+    }
 
-        let empty = self.game.empty_cells.borrow();
-        let available = self.game.owned_cells.borrow();
+    pub fn history(&self) -> Ref<'_, Vec<Move>> {
+        self.game.history()
+    }
+
+    pub fn team(&self, player: usize) -> usize {
+        self.game.team(player)
+    }
 
-        empty
-            .union(available.get(player).unwrap())
-            .map(|x| *x)
+    /// Other players on the current player's team.
+    pub fn teammates(&self) -> Vec<usize> {
+        let player = self.game.current_player();
+        (0..self.game.players())
+            .filter(|&other| other != player && self.game.team(other) == self.game.team(player))
             .collect()
     }
+}
+
+/// Results of a batch of games.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RunReport {
+    /// Wins indexed by player. In team games only the player named by
+    /// `Outcome::Win` is credited.
+    pub wins: Vec<usize>,
+    /// Wins indexed by team. Same as `wins` unless teams are configured.
+    pub team_wins: Vec<usize>,
+    pub draws: usize,
+    pub aborted: usize,
+}
+
+impl RunReport {
+    pub fn new(teams: &Teams) -> RunReport {
+        RunReport {
+            wins: vec![0; teams.assignment.len()],
+            team_wins: vec![0; teams.count()],
+            draws: 0,
+            aborted: 0,
+        }
+    }
+
+    pub fn games(&self) -> usize {
+        self.wins.iter().sum::<usize>() + self.draws + self.aborted
+    }
+
+    pub fn add(&mut self, outcome: Outcome, teams: &Teams) {
+        match outcome {
+            Outcome::Win(winner) => {
+                self.wins[winner] += 1;
+                self.team_wins[teams.assignment[winner]] += 1;
+            }
+            Outcome::Draw => self.draws += 1,
+            Outcome::Aborted => self.aborted += 1,
+        }
+    }
 
-    pub fn capacity(&self, pos: &Pos) -> u32 {
-        *self.game.capacity.get(pos).unwrap()
+    /// Adds up reports of games between the same players.
+    pub fn merge(&mut self, other: &RunReport) {
+        for (total, wins) in self.wins.iter_mut().zip(&other.wins) {
+            *total += wins;
+        }
+        for (total, wins) in self.team_wins.iter_mut().zip(&other.team_wins) {
+            *total += wins;
+        }
+        self.draws += other.draws;
+        self.aborted += other.aborted;
     }
+}
+
+/// Something that happened while a `Runner` was playing games.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunEvent<'e> {
+    /// A game, numbered from 1 within the run, was set up.
+    GameStarted {
+        game: u32,
+    },
+    MoveMade {
+        player: usize,
+        pos: Pos,
+    },
+    /// The move that was just made set off an explosion or took over an
+    /// opponent's cell.
+    Exploded(&'e Explosion),
+    PlayerEliminated(Elimination),
+    /// A game ended. `report` already counts it.
+    GameFinished {
+        game: u32,
+        outcome: Outcome,
+        report: &'e RunReport,
+    },
+}
 
-    pub fn mass(&self, pos: &Pos) -> u32 {
-        let field = self.game.grid.get(pos).unwrap();
-        let field = field.get();
+/// Receives the events of a run as they happen.
+pub trait RunObserver {
+    /// Called with the game as it stands right after `event`.
+    fn observe(&mut self, event: &RunEvent, game: &ChainReaction);
 
-        let mass = match field {
-            Field::Empty => 0,
-            Field::Owned(data) => data.count,
-        };
+    /// Checked before every move and after every game. Returning `true`
+    /// ends the run early, dropping the game in progress if there is one.
+    fn should_stop(&mut self) -> bool {
+        false
+    }
+}
+
+impl RunObserver for () {
+    fn observe(&mut self, _event: &RunEvent, _game: &ChainReaction) {}
+}
 
-        mass
+impl<T: RunObserver + ?Sized> RunObserver for &mut T {
+    fn observe(&mut self, event: &RunEvent, game: &ChainReaction) {
+        (**self).observe(event, game)
     }
 
-    pub fn neighbors(&self, pos: &Pos) -> Vec<(Pos, &Cell<Field>)> {
-        self.game.neighbors(pos)
+    fn should_stop(&mut self) -> bool {
+        (**self).should_stop()
     }
 }
 
-pub struct Runner<'a> {
+/// Passes every event to both observers. The run stops if either of them
+/// asks it to.
+impl<A: RunObserver, B: RunObserver> RunObserver for (A, B) {
+    fn observe(&mut self, event: &RunEvent, game: &ChainReaction) {
+        self.0.observe(event, game);
+        self.1.observe(event, game);
+    }
+
+    fn should_stop(&mut self) -> bool {
+        let first = self.0.should_stop();
+        self.1.should_stop() || first
+    }
+}
+
+/// Everything a runner carries over from one game to the next. Loading it
+/// into a runner with the same settings continues the run exactly where it
+/// was saved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunnerState {
+    pub seed: [u8; 32],
+    pub stream: u64,
+    /// Position of the generator within its stream, in 32-bit words.
+    pub word_pos: u128,
+    /// State of every player, as returned by `Player::save_state`.
+    pub players: Vec<String>,
+}
+
+pub struct Runner {
     width: usize,
     height: usize,
     game: ChainReaction,
     players: Vec<Box<dyn Player>>,
-
-    on_game_finished: Option<Box<dyn Fn(&Vec<usize>, &usize, &u32) + 'a>>,
-    should_stop: Option<Box<dyn Fn() -> bool + 'a>>,
+    rng: ChaCha8Rng,
+    rules: Rules,
+    capacity: Option<Array2D<u32>>,
+    limits: Limits,
+    teams: Teams,
 }
 
-impl<'a> Runner<'a> {
+impl Runner {
     pub fn new(
         width: usize,
         height: usize,
         players: Vec<Box<dyn Player>>,
-        on_game_finished: Option<Box<dyn Fn(&Vec<usize>, &usize, &u32) + 'a>>,
-        should_stop: Option<Box<dyn Fn() -> bool + 'a>>,
-    ) -> Result<Runner<'a>, &'static str> {
+    ) -> Result<Runner, &'static str> {
         let game = ChainReaction::new(width, height, players.len())?;
+        let teams = Teams::solo(players.len());
 
         Ok(Runner {
             width,
             height,
             game,
             players,
-
-            on_game_finished,
-            should_stop,
+            rng: ChaCha8Rng::from_entropy(),
+            rules: Rules::default(),
+            capacity: None,
+            limits: Limits::default(),
+            teams,
         })
     }
 
-    pub fn run_js(
-        &mut self,
-        times: u32,
-        id_array: &js_sys::Uint32Array,
-        tally_array: &js_sys::Uint32Array,
-    ) -> Result<Vec<usize>, &'static str> {
-        let mut tally = vec![0; self.players.len()];
-        for id in 1..times + 1 {
-            while self.game.active() {
-                let player = self.game.current_player();
-                let context = GameContext::new(&self.game);
-                if self.game.player_is_alive(player) && context.available_cells().len() > 0 {
-                    let player_move = self.players[player].play(context)?;
-                    self.game.place(&player_move)?;
-                } else {
-                    self.game.skip_player();
-                }
-            }
-
-            let winner = self.game.winner()?;
-            *tally.get_mut(winner).unwrap() += 1;
-            let winner_u32: u32 = winner.try_into().unwrap();
-            tally_array.set_index(winner_u32, tally_array.get_index(winner_u32) + 1);
-            id_array.set_index(0, id);
+    /// The most recently played game. It is kept around after `run`
+    /// returns so that its history can be inspected.
+    pub fn game(&self) -> &ChainReaction {
+        &self.game
+    }
 
-            if id_array.get_index(1) != 0 {
-                return Ok(tally);
-            }
+    /// Reseeds the generator handed to players, making subsequent games
+    /// reproducible.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+    }
 
-            self.game = ChainReaction::new(self.width, self.height, self.players.len())?;
+    pub fn save_state(&self) -> RunnerState {
+        RunnerState {
+            seed: self.rng.get_seed(),
+            stream: self.rng.get_stream(),
+            word_pos: self.rng.get_word_pos(),
+            players: self.players.iter().map(|p| p.save_state()).collect(),
         }
-        Ok(tally)
     }
 
-    pub fn run(&mut self, times: u32) -> Result<Vec<usize>, &'static str> {
-        let mut tally = vec![0; self.players.len()];
-        for id in 1..times + 1 {
-            while self.game.active() {
-                let player = self.game.current_player();
-                let context = GameContext::new(&self.game);
-                if self.game.player_is_alive(player) && context.available_cells().len() > 0 {
-                    let player_move = self.players[player].play(context)?;
-                    self.game.place(&player_move)?;
-                } else {
-                    self.game.skip_player();
+    pub fn load_state(&mut self, state: &RunnerState) -> Result<(), &'static str> {
+        if state.players.len() != self.players.len() {
+            return Err("Saved state is for a different number of players.");
+        }
+        // Players check their state as they load it, so the ones loaded
+        // before a rejected state are put back to leave the runner as it was.
+        let previous: Vec<_> = self.players.iter().map(|p| p.save_state()).collect();
+        for (i, saved) in state.players.iter().enumerate() {
+            if let Err(err) = self.players[i].load_state(saved) {
+                for (player, previous) in self.players.iter_mut().zip(&previous).take(i) {
+                    player.load_state(previous)?;
                 }
+                return Err(err);
             }
+        }
+
+        self.rng = ChaCha8Rng::from_seed(state.seed);
+        self.rng.set_stream(state.stream);
+        self.rng.set_word_pos(state.word_pos);
+        Ok(())
+    }
 
-            let winner = self.game.winner()?;
-            *tally.get_mut(winner).unwrap() += 1;
+    /// Plays every game from now on under `rules`.
+    pub fn set_rules(&mut self, rules: Rules) -> Result<(), &'static str> {
+        self.rules = rules;
+        self.reset()
+    }
+
+    /// Plays every game from now on with the given capacities, or the
+    /// automatic ones if `None`.
+    pub fn set_capacity(&mut self, capacity: Option<Array2D<u32>>) -> Result<(), &'static str> {
+        self.capacity = capacity;
+        self.reset()
+    }
+
+    /// Applies `limits` to every game played from now on.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Plays every game from now on with the given alliances.
+    pub fn set_teams(&mut self, teams: Teams) -> Result<(), &'static str> {
+        self.game.set_teams(teams.clone())?;
+        self.teams = teams;
+        Ok(())
+    }
 
-            if let Some(on_game_finished) = &self.on_game_finished {
-                on_game_finished(&tally, &winner, &id);
+    fn reset(&mut self) -> Result<(), &'static str> {
+        self.game =
+            ChainReaction::with_rules(self.width, self.height, self.players.len(), self.rules)?;
+        if let Some(capacity) = &self.capacity {
+            self.game.set_capacity(capacity.clone())?;
+        }
+        self.game.set_limits(self.limits);
+        self.game.set_teams(self.teams.clone())
+    }
+
+    /// Plays the current game to the end, or returns `None` if `observer`
+    /// stops it first.
+    fn play_game(
+        &mut self,
+        observer: &mut dyn RunObserver,
+    ) -> Result<Option<Outcome>, &'static str> {
+        let mut eliminated = self.game.eliminations().len();
+        while self.game.active() {
+            if observer.should_stop() {
+                return Ok(None);
             }
 
-            if let Some(should_stop) = &self.should_stop {
-                if should_stop() {
-                    return Ok(tally);
+            let player = self.game.current_player();
+            if self.game.player_is_alive(player) && self.game.legal_move_count() > 0 {
+                let context = GameContext::new(&self.game, &mut self.rng);
+                let pos = self.players[player].play(context)?;
+                let explosion = self.game.place_traced(&pos)?;
+                observer.observe(&RunEvent::MoveMade { player, pos }, &self.game);
+                if explosion != Explosion::default() {
+                    observer.observe(&RunEvent::Exploded(&explosion), &self.game);
                 }
+            } else {
+                self.game.skip_player();
             }
 
-            self.game = ChainReaction::new(self.width, self.height, self.players.len())?;
+            let eliminations = self.game.eliminations();
+            for &elimination in &eliminations[eliminated..] {
+                observer.observe(&RunEvent::PlayerEliminated(elimination), &self.game);
+            }
+            eliminated = eliminations.len();
+        }
+
+        self.game
+            .outcome()
+            .map(Some)
+            .ok_or("Game is still in progress.")
+    }
+
+    /// Plays out an existing position under the runner's limits.
+    pub fn play_from(&mut self, mut game: ChainReaction) -> Result<Outcome, &'static str> {
+        if game.width() != self.width
+            || game.height() != self.height
+            || game.players() != self.players.len()
+        {
+            return Err("Position does not match the runner.");
+        }
+        if game.rules() != self.rules {
+            return Err("Position is played under different rules.");
+        }
+        if game.custom_capacity() != self.capacity.as_ref() {
+            return Err("Position has different capacities.");
         }
-        Ok(tally)
+
+        game.set_limits(self.limits);
+        game.set_teams(self.teams.clone())?;
+        self.game = game;
+        self.play_game(&mut ())?.ok_or("Game is still in progress.")
+    }
+
+    pub fn run(&mut self, times: u32) -> Result<RunReport, &'static str> {
+        self.run_observed(times, &mut ())
+    }
+
+    /// Same as `run`, but reports everything that happens in the games to
+    /// `observer`, which may also end the run after any game.
+    pub fn run_observed(
+        &mut self,
+        times: u32,
+        observer: &mut dyn RunObserver,
+    ) -> Result<RunReport, &'static str> {
+        let mut report = RunReport::new(&self.teams);
+        for id in 1..times + 1 {
+            self.reset()?;
+            observer.observe(&RunEvent::GameStarted { game: id }, &self.game);
+            let outcome = match self.play_game(observer)? {
+                Some(outcome) => outcome,
+                None => break,
+            };
+            report.add(outcome, &self.teams);
+
+            let event = RunEvent::GameFinished {
+                game: id,
+                outcome,
+                report: &report,
+            };
+            observer.observe(&event, &self.game);
+            if observer.should_stop() {
+                break;
+            }
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_rejects_cells_off_the_board() {
+        let game = ChainReaction::new(3, 3, 2).unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let ctx = GameContext::new(&game, &mut rng);
+
+        assert_eq!(ctx.capacity(&Pos(1, 1)), Ok(4));
+        assert_eq!(ctx.mass(&Pos(1, 1)), Ok(0));
+        assert!(ctx.capacity(&Pos(3, 0)).is_err());
+        assert!(ctx.mass(&Pos(0, 3)).is_err());
     }
 }
@@ -0,0 +1,199 @@
+//! An interactive game for the web app, played one move at a time.
+//!
+//! Cells cross the boundary as their index in row major order, the same
+//! numbering the TypeScript engine uses.
+
+use crate::{
+    array2d::Pos,
+    chain_reaction::{ChainReaction, Explosion, Field, FieldData, Outcome},
+    notation::GameRecord,
+    runner::{GameContext, Player},
+    spec::from_spec,
+    wasm::js_error,
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use std::collections::{hash_map::Entry, HashMap};
+use wasm_bindgen::prelude::*;
+
+/// Cells that exploded and were captured by a move.
+#[wasm_bindgen]
+pub struct ExplosionTrace {
+    waves: Vec<Vec<u32>>,
+    captured: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl ExplosionTrace {
+    /// Cells that exploded together in each wave, in order.
+    pub fn waves(&self) -> js_sys::Array {
+        self.waves
+            .iter()
+            .map(|wave| js_sys::Uint32Array::from(&wave[..]))
+            .collect()
+    }
+
+    pub fn captured(&self) -> Vec<u32> {
+        self.captured.clone()
+    }
+}
+
+#[wasm_bindgen]
+pub struct GameSession {
+    game: ChainReaction,
+    /// Bots asked for moves so far, by spec and the player they played
+    /// for, so that bots which plan ahead keep their plans. Undoing a move
+    /// drops them, as their plans may rest on the move taken back.
+    bots: HashMap<(String, usize), Box<dyn Player>>,
+    rng: ChaCha8Rng,
+}
+
+#[wasm_bindgen]
+impl GameSession {
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: usize, height: usize, players: usize) -> Result<GameSession, JsValue> {
+        Ok(GameSession {
+            game: ChainReaction::new(width, height, players).map_err(js_error)?,
+            bots: HashMap::new(),
+            rng: ChaCha8Rng::from_entropy(),
+        })
+    }
+
+    /// Makes bot moves reproducible.
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+    }
+
+    /// Places an orb for the current player, skipping players who are out
+    /// or have no moves afterwards.
+    pub fn place(&mut self, x: usize, y: usize) -> Result<ExplosionTrace, JsValue> {
+        let explosion = self.game.place_traced(&Pos(x, y)).map_err(js_error)?;
+        self.skip_stuck_players();
+
+        let Explosion {
+            waves, captured, ..
+        } = explosion;
+        Ok(ExplosionTrace {
+            waves: waves
+                .iter()
+                .map(|wave| wave.iter().map(|pos| self.index(pos)).collect())
+                .collect(),
+            captured: captured.iter().map(|pos| self.index(pos)).collect(),
+        })
+    }
+
+    pub fn current_player(&self) -> usize {
+        self.game.current_player()
+    }
+
+    /// Two entries per cell: its owner plus one, or zero if it is empty,
+    /// followed by the number of orbs in it.
+    pub fn cells(&self) -> Vec<u32> {
+        self.game
+            .grid
+            .iter()
+            .flat_map(|cell| match cell.get() {
+                Field::Empty => [0, 0],
+                Field::Owned(FieldData { owner, count }) => [owner as u32 + 1, count],
+            })
+            .collect()
+    }
+
+    /// Cells the current player can place an orb on.
+    pub fn legal_moves(&self) -> Vec<u32> {
+        let mut moves: Vec<_> = self
+            .game
+            .legal_moves()
+            .map(|pos| self.index(&pos))
+            .collect();
+        moves.sort_unstable();
+        moves
+    }
+
+    /// Takes back the last move.
+    pub fn undo(&mut self) -> Result<(), JsValue> {
+        let mut record = GameRecord::from_game(&self.game);
+        if record.moves.pop().is_none() {
+            return Err(js_error("There is no move to undo."));
+        }
+        record.outcome = None;
+
+        self.game = record.replay().map_err(js_error)?;
+        self.bots.clear();
+        self.skip_stuck_players();
+        Ok(())
+    }
+
+    /// The winning player once the game is won.
+    pub fn winner(&self) -> Option<usize> {
+        match self.game.outcome() {
+            Some(Outcome::Win(winner)) => Some(winner),
+            _ => None,
+        }
+    }
+
+    /// Cell that the strategy given by `spec`, such as `form_chains`, would
+    /// play for the current player. The move is not made.
+    pub fn ask_bot(&mut self, spec: &str) -> Result<u32, JsValue> {
+        if !self.game.active() {
+            return Err(js_error("The game is finished."));
+        }
+
+        let key = (spec.to_string(), self.game.current_player());
+        let bot = match self.bots.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(from_spec(spec).map_err(js_error)?),
+        };
+        let pos = bot
+            .play(GameContext::new(&self.game, &mut self.rng))
+            .map_err(js_error)?;
+        Ok(self.index(&pos))
+    }
+}
+
+impl GameSession {
+    fn index(&self, &Pos(x, y): &Pos) -> u32 {
+        (y * self.game.width() + x) as u32
+    }
+
+    fn skip_stuck_players(&self) {
+        while self.game.active()
+            && (!self.game.player_is_alive(self.game.current_player())
+                || self.game.legal_move_count() == 0)
+        {
+            self.game.skip_player();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plays_undoes_and_asks_bots() {
+        let mut session = GameSession::new(3, 3, 2).unwrap();
+        session.seed(1);
+        session.place(0, 0).unwrap();
+        assert_eq!(session.current_player(), 1);
+        assert_eq!(&session.cells()[..2], &[1, 1]);
+        assert!(!session.legal_moves().contains(&0));
+
+        let trace = session.place(2, 2).unwrap();
+        assert!(trace.waves.is_empty());
+        let trace = session.place(0, 0).unwrap();
+        assert_eq!(trace.waves, vec![vec![0]]);
+        assert!(trace.captured.is_empty());
+
+        session.undo().unwrap();
+        assert_eq!(&session.cells()[..2], &[1, 1]);
+        assert_eq!(session.current_player(), 0);
+
+        let choice = session.ask_bot("form_chains").unwrap();
+        assert!(session.legal_moves().contains(&choice));
+        assert_eq!(session.winner(), None);
+
+        session.undo().unwrap();
+        assert!(session.bots.is_empty());
+    }
+}
@@ -0,0 +1,237 @@
+//! Player specifications: a strategy name, optionally followed by
+//! parameters in parentheses, such as `form_chains` or
+//! `name(key=value, other=value)`.
+
+use crate::{
+    players::{AvoidOthers, FormChains, PlayRandomly},
+    Player,
+};
+use std::{fmt, str::FromStr};
+
+/// A parameter that a strategy accepts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ParamInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub default: &'static str,
+}
+
+/// A built-in strategy along with the parameters it accepts.
+#[derive(Clone, Copy, Debug)]
+pub struct StrategyInfo {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub params: &'static [ParamInfo],
+    /// Builds the player from a spec naming this strategy, whose parameters
+    /// are known to be among `params`.
+    pub build: fn(&PlayerSpec) -> Result<Box<dyn Player>, &'static str>,
+}
+
+pub const STRATEGY_INFO: [StrategyInfo; 3] = [
+    StrategyInfo {
+        name: "play_randomly",
+        description: "Plays on a random cell.",
+        params: &[],
+        build: |_| Ok(Box::new(PlayRandomly {})),
+    },
+    StrategyInfo {
+        name: "avoid_others",
+        description: "Plays where the neighbouring cells hold the fewest orbs.",
+        params: &[ParamInfo {
+            name: "radius",
+            description: "How many steps away orbs are counted, 1 being just the neighbours.",
+            default: "1",
+        }],
+        build: |spec| {
+            let radius = spec.value("radius")?;
+            if radius == 0 {
+                return Err("Radius must be at least 1.");
+            }
+            Ok(Box::new(AvoidOthers { radius }))
+        },
+    },
+    StrategyInfo {
+        name: "form_chains",
+        description: "Fills cells up to one orb short of exploding, one chain at a time.",
+        params: &[],
+        build: |_| Ok(Box::new(FormChains::new())),
+    },
+];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlayerSpec {
+    pub name: String,
+    /// Parameters in the order they were given.
+    pub params: Vec<(String, String)>,
+}
+
+fn is_identifier(input: &str) -> bool {
+    input.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && input
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+impl PlayerSpec {
+    pub fn parse(input: &str) -> Result<PlayerSpec, &'static str> {
+        let input = input.trim();
+        let (name, params) = match input.find('(') {
+            Some(open) => {
+                let params = input[open + 1..]
+                    .strip_suffix(')')
+                    .ok_or("Player spec is missing a closing parenthesis.")?;
+                (input[..open].trim_end(), Some(params))
+            }
+            None => (input, None),
+        };
+
+        if !is_identifier(name) {
+            return Err("Invalid strategy name.");
+        }
+
+        let mut spec = PlayerSpec {
+            name: name.to_string(),
+            params: Vec::new(),
+        };
+        let params = params.map(str::trim).filter(|params| !params.is_empty());
+        for param in params.into_iter().flat_map(|params| params.split(',')) {
+            let (key, value) = param
+                .split_once('=')
+                .ok_or("Parameters must be given as key=value.")?;
+            let (key, value) = (key.trim(), value.trim());
+            if !is_identifier(key) || value.is_empty() || value.contains(['(', ')', '=']) {
+                return Err("Parameters must be given as key=value.");
+            }
+            if spec.params.iter().any(|(other, _)| other == key) {
+                return Err("Parameter is given more than once.");
+            }
+            spec.params.push((key.to_string(), value.to_string()));
+        }
+
+        Ok(spec)
+    }
+
+    pub fn info(&self) -> Result<&'static StrategyInfo, &'static str> {
+        STRATEGY_INFO
+            .iter()
+            .find(|info| info.name == self.name)
+            .ok_or("Unrecognized player.")
+    }
+
+    pub fn build(&self) -> Result<Box<dyn Player>, &'static str> {
+        let info = self.info()?;
+        for (key, _) in &self.params {
+            if !info.params.iter().any(|param| param.name == key) {
+                return Err("Unknown strategy parameter.");
+            }
+        }
+
+        (info.build)(self)
+    }
+
+    /// The value given for a parameter, or its default.
+    fn value<T: FromStr>(&self, name: &str) -> Result<T, &'static str> {
+        let value = match self.params.iter().find(|(key, _)| key == name) {
+            Some((_, value)) => value.as_str(),
+            None => self
+                .info()?
+                .params
+                .iter()
+                .find(|param| param.name == name)
+                .map_or("", |param| param.default),
+        };
+        value
+            .parse()
+            .map_err(|_| "Invalid strategy parameter value.")
+    }
+}
+
+impl fmt::Display for PlayerSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.params.is_empty() {
+            let params: Vec<_> = self
+                .params
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect();
+            write!(f, "({})", params.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds a player from its spec.
+pub fn from_spec(spec: &str) -> Result<Box<dyn Player>, &'static str> {
+    PlayerSpec::parse(spec)?.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{notation::parse_position, GameContext, Pos};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    #[test]
+    fn parses_specs() {
+        let spec = PlayerSpec::parse(" mcts ( iterations=500, c = 1.4 ) ").unwrap();
+        assert_eq!(spec.name, "mcts");
+        assert_eq!(
+            spec.params,
+            vec![
+                ("iterations".to_string(), "500".to_string()),
+                ("c".to_string(), "1.4".to_string())
+            ]
+        );
+        assert_eq!(spec.to_string(), "mcts(iterations=500, c=1.4)");
+        assert_eq!(PlayerSpec::parse("form_chains()").unwrap().params, vec![]);
+
+        for invalid in &[
+            "",
+            "Form",
+            "a(",
+            "a(b)",
+            "a(b=)",
+            "a(b=1,b=2)",
+            "a(b=1,)",
+            "a)",
+        ] {
+            assert!(PlayerSpec::parse(invalid).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn builds_known_strategies_only() {
+        for info in STRATEGY_INFO.iter() {
+            assert!(from_spec(info.name).is_ok());
+        }
+        assert_eq!(from_spec("mcts").err(), Some("Unrecognized player."));
+        assert_eq!(
+            from_spec("form_chains(depth=2)").err(),
+            Some("Unknown strategy parameter.")
+        );
+    }
+
+    #[test]
+    fn passes_parameters_to_strategies() {
+        // Each radius leaves a single cell with the fewest orbs around it.
+        // With the whole board in reach, that is player 0's own cell.
+        let game = parse_position("position 3 3 2 0 3\n. 2:1 1:0\n. . .\n. 1:1 .").unwrap();
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let mut play = |spec| from_spec(spec)?.play(GameContext::new(&game, &mut rng));
+
+        assert_eq!(play("avoid_others"), Ok(Pos(0, 1)));
+        assert_eq!(play("avoid_others(radius=1)"), Ok(Pos(0, 1)));
+        assert_eq!(play("avoid_others(radius=2)"), Ok(Pos(0, 2)));
+        assert_eq!(play("avoid_others(radius=100)"), Ok(Pos(2, 0)));
+        assert_eq!(
+            play("avoid_others(radius=0)"),
+            Err("Radius must be at least 1.")
+        );
+        assert_eq!(
+            play("avoid_others(radius=far)"),
+            Err("Invalid strategy parameter value.")
+        );
+    }
+}
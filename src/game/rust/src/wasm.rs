@@ -0,0 +1,131 @@
+//! Functions exported to the web app, along with the glue for passing
+//! players and errors across the JavaScript boundary.
+
+use crate::{
+    from_spec, ChainReaction, Outcome, Player, RunEvent, RunObserver, Runner, STRATEGY_INFO,
+};
+use wasm_bindgen::prelude::*;
+
+/// Forwards panic messages to the browser console, which would otherwise
+/// only see an `unreachable` trap.
+#[wasm_bindgen(start)]
+pub fn install_panic_hook() {
+    console_error_panic_hook::set_once();
+}
+
+/// Wraps an error message in a JavaScript `Error`, so that it is thrown with
+/// a stack trace.
+pub(crate) fn js_error(message: &str) -> JsValue {
+    js_sys::Error::new(message).into()
+}
+
+/// Builds a player from every spec in `players`, such as `form_chains`.
+fn resolve_players(players: &js_sys::Array) -> Result<Vec<Box<dyn Player>>, JsValue> {
+    players
+        .iter()
+        .map(|spec| {
+            let spec = spec
+                .as_string()
+                .ok_or_else(|| js_error("Player specs must be strings."))?;
+            from_spec(&spec).map_err(|err| js_error(&format!("{}: {}", spec, err)))
+        })
+        .collect()
+}
+
+fn setup_error(err: &str) -> JsValue {
+    js_error(&format!("Could not set up the games: {}", err))
+}
+
+fn run_error(err: &str) -> JsValue {
+    js_error(&format!("A game could not be finished: {}", err))
+}
+
+/// Describes the strategies that player specs can name, along with the
+/// parameters each of them accepts.
+#[wasm_bindgen]
+pub fn list_strategies() -> Result<JsValue, JsValue> {
+    let strategies: Vec<_> = STRATEGY_INFO
+        .iter()
+        .map(|info| {
+            let params: Vec<_> = info
+                .params
+                .iter()
+                .map(|param| {
+                    serde_json::json!({
+                        "name": param.name,
+                        "description": param.description,
+                        "default": param.default,
+                    })
+                })
+                .collect();
+            serde_json::json!({
+                "name": info.name,
+                "description": info.description,
+                "params": params,
+            })
+        })
+        .collect();
+
+    js_sys::JSON::parse(&serde_json::Value::from(strategies).to_string())
+}
+
+/// Reports progress to the web app through shared memory. The first entry
+/// of the control buffer receives the number of finished games, and the web
+/// app sets the second one to stop the run. The tally buffer counts wins.
+struct SharedBufferObserver {
+    control: js_sys::Uint32Array,
+    tally: js_sys::Uint32Array,
+}
+
+impl RunObserver for SharedBufferObserver {
+    fn observe(&mut self, event: &RunEvent, _game: &ChainReaction) {
+        if let RunEvent::GameFinished { game, outcome, .. } = *event {
+            if let Outcome::Win(winner) = outcome {
+                let winner = winner as u32;
+                self.tally
+                    .set_index(winner, self.tally.get_index(winner) + 1);
+            }
+            self.control.set_index(0, game);
+        }
+    }
+
+    fn should_stop(&mut self) -> bool {
+        self.control.get_index(1) != 0
+    }
+}
+
+#[wasm_bindgen]
+pub fn run_with_shared_buffer(
+    width: usize,
+    height: usize,
+    players: &js_sys::Array,
+    times: u32,
+    control_buffer: &js_sys::SharedArrayBuffer,
+    tally_buffer: &js_sys::SharedArrayBuffer,
+) -> Result<Vec<usize>, JsValue> {
+    let mut observer = SharedBufferObserver {
+        control: js_sys::Uint32Array::new(control_buffer),
+        tally: js_sys::Uint32Array::new(tally_buffer),
+    };
+
+    let players = resolve_players(players)?;
+    let mut runner = Runner::new(width, height, players).map_err(setup_error)?;
+
+    Ok(runner
+        .run_observed(times, &mut observer)
+        .map_err(run_error)?
+        .wins)
+}
+
+#[wasm_bindgen]
+pub fn run(
+    width: usize,
+    height: usize,
+    players: &js_sys::Array,
+    times: u32,
+) -> Result<Vec<usize>, JsValue> {
+    let players = resolve_players(players)?;
+    let mut runner = Runner::new(width, height, players).map_err(setup_error)?;
+
+    Ok(runner.run(times).map_err(run_error)?.wins)
+}
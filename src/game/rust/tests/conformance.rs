@@ -0,0 +1,27 @@
+//! Checks that the stored conformance corpus still replays to the positions
+//! and results it was generated with. Regenerate it with
+//! `cargo run -- conformance generate` after an intentional rule change.
+
+use chain_reaction::ConformanceCase;
+use std::{fs, path::Path};
+
+#[test]
+fn stored_corpus_replays() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("conformance");
+    let mut cases = 0;
+
+    for entry in fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().is_none_or(|ext| ext != "txt") {
+            continue;
+        }
+
+        let case = ConformanceCase::parse(&fs::read_to_string(&path).unwrap()).unwrap();
+        if let Err(err) = case.verify() {
+            panic!("{}: {}", path.display(), err);
+        }
+        cases += 1;
+    }
+
+    assert!(cases > 0, "no cases in {}", dir.display());
+}
@@ -0,0 +1,254 @@
+//! Plays random games and checks that the engine's bookkeeping agrees with
+//! the board after every move. Moves are generated as indices into the list
+//! of legal moves, so a failing game shrinks to the shortest sequence of
+//! moves that still breaks an invariant.
+
+use chain_reaction::{
+    Array2D, ChainReaction, Explosion, Field, FieldData, Pos, Resolution, Rules, Teams,
+};
+use proptest::prelude::*;
+
+/// Checks that the cell sets and player masses agree with the board and
+/// returns the number of orbs on it.
+fn check_invariants(game: &ChainReaction) -> Result<u64, TestCaseError> {
+    let empty = game.empty_cells.borrow();
+    let owned = game.owned_cells.borrow();
+    let mut masses = vec![0; game.players()];
+
+    for y in 0..game.height() {
+        for x in 0..game.width() {
+            let pos = Pos(x, y);
+            let owners: Vec<_> = (0..game.players())
+                .filter(|&player| owned[player].contains(&pos))
+                .collect();
+
+            match game.grid.get(&pos).unwrap().get() {
+                Field::Empty => {
+                    prop_assert!(empty.contains(&pos), "{:?} is not listed as empty", pos);
+                    prop_assert!(owners.is_empty(), "empty {:?} is owned", pos);
+                }
+                Field::Owned(FieldData { owner, count }) => {
+                    prop_assert!(!empty.contains(&pos), "{:?} is listed as empty", pos);
+                    prop_assert_eq!(owners, vec![owner], "ownership of {:?}", pos);
+                    prop_assert!(count > 0, "{:?} is owned without mass", pos);
+                    prop_assert!(
+                        count < *game.capacity().get(&pos).unwrap(),
+                        "{:?} was left at capacity",
+                        pos
+                    );
+                    masses[owner] += count;
+                }
+            }
+        }
+    }
+
+    let cells = game.width() * game.height();
+    prop_assert_eq!(
+        empty.len() + owned.iter().map(|cells| cells.len()).sum::<usize>(),
+        cells
+    );
+
+    for (player, &mass) in masses.iter().enumerate() {
+        if game.active() {
+            prop_assert_eq!(game.player_mass(player), mass, "mass of player {}", player);
+        } else {
+            // An explosion that ends the game leaves its orbs in flight,
+            // still counted towards their owner.
+            prop_assert!(
+                game.player_mass(player) >= mass,
+                "mass of player {}",
+                player
+            );
+        }
+    }
+
+    Ok(masses.iter().map(|&mass| u64::from(mass)).sum())
+}
+
+/// Orbs that the cells of `explosion` created by exploding, less the ones
+/// they discarded. A cell explodes once it holds its capacity and sends one
+/// orb to every neighbour. A cell without neighbours loses its orbs even
+/// when the rules keep the excess.
+fn orbs_created(game: &ChainReaction, explosion: &Explosion) -> i64 {
+    explosion
+        .waves
+        .iter()
+        .flatten()
+        .map(|pos| {
+            let held = i64::from(*game.capacity().get(pos).unwrap());
+            let sent = game.neighbors(pos).len() as i64;
+            let kept = if game.rules().keep_excess && sent > 0 {
+                (held - sent).max(0)
+            } else {
+                0
+            };
+            sent + kept - held
+        })
+        .sum()
+}
+
+/// Plays `choices` as indices into the legal moves and checks the
+/// invariants after every move.
+///
+/// Every move adds an orb and every explosion adds or removes the orbs
+/// counted by `orbs_created`. Beyond that, orbs only leave the board when an
+/// explosion is cut short: those it drops for repeating itself are taken
+/// off their owners' mass, and those it leaves in flight by ending the game
+/// are still counted towards it.
+fn play(game: &ChainReaction, choices: &[usize]) -> Result<(), TestCaseError> {
+    let mut moves = Vec::new();
+    let mut on_board = check_invariants(game)?;
+
+    for &choice in choices {
+        if !game.active() {
+            break;
+        }
+        game.legal_moves_into(&mut moves);
+        if !game.player_is_alive(game.current_player()) || moves.is_empty() {
+            game.skip_player();
+            continue;
+        }
+
+        let explosion = game.place_traced(&moves[choice % moves.len()]).unwrap();
+        let before = on_board;
+        on_board = check_invariants(game)?;
+
+        let counted: u64 = (0..game.players())
+            .map(|player| u64::from(game.player_mass(player)))
+            .sum();
+        let in_flight = counted - on_board;
+        prop_assert!(in_flight == 0 || !game.active(), "orbs left in flight");
+        prop_assert_eq!(
+            on_board as i64,
+            before as i64 + 1 + orbs_created(game, &explosion)
+                - explosion.dropped as i64
+                - in_flight as i64,
+            "orbs on the board"
+        );
+    }
+
+    Ok(())
+}
+
+fn rules() -> impl Strategy<Value = Rules> {
+    (0u32..=2, any::<bool>(), any::<bool>(), any::<bool>()).prop_map(
+        |(capacity_offset, keep_excess, depth_first, place_on_opponents)| Rules {
+            capacity_offset,
+            keep_excess,
+            resolution: if depth_first {
+                Resolution::DepthFirst
+            } else {
+                Resolution::BreadthFirst
+            },
+            place_on_opponents,
+        },
+    )
+}
+
+/// Player counts along with teams for them, numbered in the order they
+/// first appear.
+fn players_and_teams() -> impl Strategy<Value = (usize, Teams)> {
+    (2usize..=4).prop_flat_map(|players| {
+        (
+            Just(players),
+            prop::collection::vec(0..players, players),
+            any::<bool>(),
+        )
+            .prop_map(|(players, picks, convert_allies)| {
+                let mut teams: Vec<usize> = Vec::new();
+                let assignment = picks
+                    .iter()
+                    .map(|pick| {
+                        teams
+                            .iter()
+                            .position(|team| team == pick)
+                            .unwrap_or_else(|| {
+                                teams.push(*pick);
+                                teams.len() - 1
+                            })
+                    })
+                    .collect();
+                (
+                    players,
+                    Teams {
+                        assignment,
+                        convert_allies,
+                    },
+                )
+            })
+    })
+}
+
+/// Sets up a game, with bespoke capacities taken from the start of
+/// `capacities` if given.
+fn new_game(
+    width: usize,
+    height: usize,
+    players: usize,
+    rules: Rules,
+    capacities: Option<Vec<u32>>,
+) -> ChainReaction {
+    let mut game = ChainReaction::with_rules(width, height, players, rules).unwrap();
+    if let Some(capacities) = capacities {
+        let mut capacity = Array2D::new(0, Pos(width, height));
+        for (i, &c) in capacities.iter().take(width * height).enumerate() {
+            *capacity.get_mut(&Pos(i % width, i / width)).unwrap() = c;
+        }
+        game.set_capacity(capacity).unwrap();
+    }
+    game
+}
+
+proptest! {
+    #[test]
+    fn two_player_games(
+        width in 2usize..=8,
+        height in 2usize..=8,
+        choices in prop::collection::vec(any::<usize>(), 0..200),
+    ) {
+        let game = ChainReaction::new(width, height, 2).unwrap();
+        play(&game, &choices)?;
+        // Under the classic rules explosions neither create nor discard
+        // orbs, and with a single enemy none can repeat itself while the
+        // game goes on, so every orb placed is still counted.
+        let counted: u32 = (0..2).map(|player| game.player_mass(player)).sum();
+        prop_assert_eq!(counted, game.move_count());
+    }
+
+    #[test]
+    fn multiplayer_games(
+        width in 1usize..=6,
+        height in 1usize..=6,
+        players in 2usize..=4,
+        choices in prop::collection::vec(any::<usize>(), 0..200),
+    ) {
+        let game = ChainReaction::new(width, height, players).unwrap();
+        play(&game, &choices)?;
+    }
+
+    #[test]
+    fn rule_variants(
+        width in 1usize..=6,
+        height in 1usize..=6,
+        players in 2usize..=4,
+        rules in rules(),
+        capacities in prop::option::of(prop::collection::vec(1u32..=5, 36)),
+        choices in prop::collection::vec(any::<usize>(), 0..200),
+    ) {
+        let game = new_game(width, height, players, rules, capacities);
+        play(&game, &choices)?;
+    }
+
+    #[test]
+    fn team_games(
+        width in 1usize..=6,
+        height in 1usize..=6,
+        (players, teams) in players_and_teams(),
+        rules in rules(),
+        choices in prop::collection::vec(any::<usize>(), 0..200),
+    ) {
+        let mut game = new_game(width, height, players, rules, None);
+        game.set_teams(teams).unwrap();
+        play(&game, &choices)?;
+    }
+}
@@ -0,0 +1,60 @@
+//! Stops a run partway, carries its state over to a fresh runner and checks
+//! that the rest of the run plays out exactly as if it had never stopped.
+
+use chain_reaction::{from_name, GameRecord, Player, Runner};
+
+fn runner() -> Runner {
+    let players: Vec<Box<dyn Player>> = ["form_chains", "play_randomly", "form_chains"]
+        .iter()
+        .map(|name| from_name(name).unwrap())
+        .collect();
+    let mut runner = Runner::new(5, 4, players).unwrap();
+    runner.seed(7);
+    runner
+}
+
+#[test]
+fn resumed_run_matches_uninterrupted_run() {
+    let mut uninterrupted = runner();
+    let expected = uninterrupted.run(30).unwrap();
+    let last_game = GameRecord::from_game(uninterrupted.game());
+
+    let mut first = runner();
+    let mut report = first.run(11).unwrap();
+    let state = first.save_state();
+    assert!(state.players[0] != "-");
+
+    let mut second = runner();
+    second.load_state(&state).unwrap();
+    report.merge(&second.run(19).unwrap());
+
+    assert_eq!(report, expected);
+    assert_eq!(GameRecord::from_game(second.game()), last_game);
+    assert_eq!(second.save_state(), uninterrupted.save_state());
+}
+
+#[test]
+fn rejects_mismatched_state() {
+    let mut state = runner().save_state();
+    state.players.pop();
+    assert!(runner().load_state(&state).is_err());
+
+    let mut state = runner().save_state();
+    state.players[1] = "1 2".to_string();
+    assert!(runner().load_state(&state).is_err());
+    state.players[1] = String::new();
+    state.players[0] = "1".to_string();
+    assert!(runner().load_state(&state).is_err());
+
+    // A rejected state leaves the runner as it was, including the players
+    // whose state was fine.
+    let mut runner = runner();
+    runner.run(3).unwrap();
+    let before = runner.save_state();
+    let mut state = before.clone();
+    state.players[0] = "4 3".to_string();
+    state.players[2] = "x".to_string();
+    state.seed = [1; 32];
+    assert!(runner.load_state(&state).is_err());
+    assert_eq!(runner.save_state(), before);
+}
@@ -25,24 +25,22 @@ const state: State = {
 	runsPerBatch: 0,
 };
 
+/** Converts a player name into a spec understood by the Rust engine. */
 function mapWasmPlayer(player: WasmPlayerName) {
 	switch (player) {
 		case "PlayRandomly":
-			return 0;
+			return "play_randomly";
 		case "AvoidOthers":
-			return 1;
+			return "avoid_others";
 		case "FormChains":
-			return 2;
+			return "form_chains";
 		default:
 			throw new Error(`Player ${player} isn't supported in WASM mode.`);
 	}
 }
 
 function resolvePlayers(players: readonly WasmPlayerName[]) {
-	const result = new Uint32Array(players.length);
-
-	players.forEach((player, i) => (result[i] = mapWasmPlayer(player)));
-	return result;
+	return players.map(mapWasmPlayer);
 }
 
 /**
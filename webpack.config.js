@@ -94,6 +94,7 @@ module.exports = function (env, argv) {
 			}),
 			new WasmPackPlugin({
 				crateDirectory: path("src/game/rust"),
+				extraArgs: "-- --no-default-features --features wasm",
 			}),
 			new MiniCssExtractPlugin(),
 